@@ -1,7 +1,7 @@
 use clap::{crate_name, crate_version, App, Arg};
 use env_logger::{Builder, Env};
 
-use zoinks_lib::schemafile2print;
+use zoinks_lib::{Config, Grouping, Merge, Options, read_schema, schemafile2dir, schemafile2print_with_options, unsupported_keywords_with_options};
 // use zoinks_codegen::schema2struct;
 
 fn main() {
@@ -17,9 +17,130 @@ fn main() {
                 .takes_value(true)
                 .required(true),
         )
+        .arg(
+            Arg::with_name("root")
+                .short("r")
+                .long("root")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Only generates types reachable from this definition (a name or a JSON pointer, # for the schema itself)"),
+        )
+        .arg(
+            Arg::with_name("out-dir")
+                .short("o")
+                .long("out-dir")
+                .takes_value(true)
+                .help("Writes a module per definition (or group) and a mod.rs to this directory instead of printing"),
+        )
+        .arg(
+            Arg::with_name("group-by")
+                .long("group-by")
+                .takes_value(true)
+                .possible_values(&["definition", "first-word"])
+                .default_value("definition")
+                .help("Which definitions share a module"),
+        )
+        .arg(
+            Arg::with_name("merge")
+                .long("merge")
+                .takes_value(true)
+                .possible_values(&["never", "identical", "aliases"])
+                .default_value("never")
+                .help("Whether types that only differ in name are merged, and whether the merged names are kept as aliases"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .takes_value(true)
+                .help("JSON file with names to use, existing types to substitute and definitions to skip"),
+        )
+        .arg(
+            Arg::with_name("draft")
+                .long("draft")
+                .takes_value(true)
+                .possible_values(&["4", "6", "7", "2019-09", "2020-12"])
+                .help("Reads the schema as this draft instead of the one $schema names"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["json", "yaml", "json5"])
+                .help("What the schema is written in, instead of going by its extension (.yaml/.yml, .json5 or JSON)"),
+        )
+        .arg(
+            Arg::with_name("openapi")
+                .long("openapi")
+                .help("Reads the input as an OpenAPI 3.0 or 3.1 document and generates types for its components/schemas"),
+        )
+        .arg(
+            Arg::with_name("unsupported")
+                .long("unsupported")
+                .help("Lists every keyword the generated types won't enforce on stderr"),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("Like --unsupported, but fails without generating anything if there are any"),
+        )
         .get_matches();
 
     let in_file = matches.value_of("input").unwrap();
 
-    println!("{}", schemafile2print(in_file));
+    let config = match matches.value_of("config") {
+        Some(config_file) => Config::from_file(config_file).unwrap_or_else(|e| exit_with(format!("Unable to read {}: {}", config_file, e))),
+        None => Config::default(),
+    };
+
+    let options = Options {
+        roots: matches.values_of("root").map(|roots| roots.map(String::from).collect()).unwrap_or_default(),
+        grouping: match matches.value_of("group-by") {
+            Some("first-word") => Grouping::FirstWord,
+            _ => Grouping::Definition,
+        },
+        merge: match matches.value_of("merge") {
+            Some("identical") => Merge::Identical,
+            Some("aliases") => Merge::IdenticalWithAliases,
+            _ => Merge::Never,
+        },
+        config,
+        draft: matches.value_of("draft").map(|draft| draft.parse().unwrap_or_else(|e| exit_with(e))),
+        format: matches.value_of("format").map(|format| format.parse().unwrap_or_else(|e| exit_with(e))),
+        openapi: matches.is_present("openapi"),
+        strict: matches.is_present("strict"),
+    };
+
+    // Read up front so a schema that doesn't parse is reported as such, whatever is done with it
+    let schema_string = std::fs::read_to_string(in_file).unwrap_or_else(|e| exit_with(format!("Unable to read {}: {}", in_file, e)));
+    let schema = read_schema(in_file, &schema_string, &options).unwrap_or_else(|e| exit_with(format!("Unable to parse {}: {}", in_file, e)));
+
+    if matches.is_present("unsupported") || matches.is_present("strict") {
+        let unsupported = unsupported_keywords_with_options(&schema, &options);
+        for diagnostic in unsupported.iter() {
+            eprintln!("{}", diagnostic);
+        }
+
+        if matches.is_present("strict") && !unsupported.is_empty() {
+            exit_with(format!("{} keywords won't be enforced", unsupported.len()));
+        }
+    }
+
+    match matches.value_of("out-dir") {
+        Some(out_dir) => {
+            if let Err(e) = schemafile2dir(in_file, out_dir, &options) {
+                exit_with(format!("Unable to write {}: {}", out_dir, e));
+            }
+        },
+        None => match schemafile2print_with_options(in_file, &options) {
+            Ok(bindings) => println!("{}", bindings),
+            Err(e) => exit_with(format!("Unable to generate bindings for {}: {}", in_file, e)),
+        },
+    }
+}
+
+fn exit_with<M: std::fmt::Display>(message: M) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
 }
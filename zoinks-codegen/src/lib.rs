@@ -1,17 +1,53 @@
 use proc_macro::TokenStream;
-use syn::LitStr;
+use syn::{Ident, LitBool, LitStr, Token};
 use syn::parse_macro_input;
 use syn::parse::{Parse, ParseStream, Result};
 
-use zoinks_lib::{Schema, genimpl};
+use zoinks_lib::{Config, Format, Options, genimpl_with_options, read_schema, unsupported_keywords_with_options};
 
+// schema2struct!("schema.json") or schema2struct!("schema.json", "config.json"), either followed by
+// `format = "yaml"` for schemas whose extension doesn't say what they're written in, `openapi = true` for OpenAPI
+// documents and `strict = true` to fail on keywords that won't be enforced
 struct Args {
-    input_fn: String,
+    input_fn: LitStr,
+    config_fn: Option<LitStr>,
+    format: Option<Format>,
+    openapi: bool,
+    strict: bool,
 }
 impl Parse for Args {
     fn parse(input: ParseStream) -> Result<Self> {
-        let input_fn = input.parse::<LitStr>()?.value();
-        Ok(Self { input_fn })
+        let input_fn = input.parse::<LitStr>()?;
+        let mut config_fn = None;
+        let mut format = None;
+        let mut openapi = false;
+        let mut strict = false;
+
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            if input.peek(LitStr) && config_fn.is_none() && format.is_none() && !openapi && !strict {
+                config_fn = Some(input.parse::<LitStr>()?);
+                continue
+            }
+
+            let key = input.parse::<Ident>()?;
+            if key == "openapi" || key == "strict" {
+                input.parse::<Token![=]>()?;
+                let value = input.parse::<LitBool>()?.value;
+                match key == "openapi" {
+                    true => openapi = value,
+                    false => strict = value,
+                }
+                continue
+            }
+            if key != "format" {
+                return Err(syn::Error::new(key.span(), "Expected a config file, format = \"...\", openapi = true or strict = true"))
+            }
+            input.parse::<Token![=]>()?;
+            let value = input.parse::<LitStr>()?;
+            format = Some(value.value().parse::<Format>().map_err(|e| syn::Error::new(value.span(), e))?);
+        }
+
+        Ok(Self { input_fn, config_fn, format, openapi, strict })
     }
 }
 
@@ -19,9 +55,36 @@ impl Parse for Args {
 pub fn schema2struct(input: TokenStream) -> TokenStream {
     let args = parse_macro_input!(input as Args);
 
-    let schema_string = std::fs::read_to_string(args.input_fn).unwrap();
-    let schema : Schema = serde_json::from_str(&schema_string).unwrap();
+    let input_fn = args.input_fn.value();
+    let schema_string = match std::fs::read_to_string(&input_fn) {
+        Ok(schema_string) => schema_string,
+        Err(e) => return syn::Error::new(args.input_fn.span(), format!("Unable to read {}: {}", input_fn, e)).to_compile_error().into(),
+    };
+
+    let options = Options { format: args.format, openapi: args.openapi, strict: args.strict, ..Default::default() };
+    let schema = match read_schema(&input_fn, &schema_string, &options) {
+        Ok(schema) => schema,
+        Err(e) => return syn::Error::new(args.input_fn.span(), format!("Unable to parse {}: {}", input_fn, e)).to_compile_error().into(),
+    };
+
+    let config = match args.config_fn {
+        Some(config_fn) => match Config::from_file(config_fn.value()) {
+            Ok(config) => config,
+            Err(e) => return syn::Error::new(config_fn.span(), format!("Unable to read {}: {}", config_fn.value(), e)).to_compile_error().into(),
+        },
+        None => Config::default(),
+    };
+
+    let options = Options { config, ..options };
+    if options.strict {
+        let unsupported = unsupported_keywords_with_options(&schema, &options);
+        if !unsupported.is_empty() {
+            let message = unsupported.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n");
+            let message = format!("{} keywords in {} won't be enforced:\n{}", unsupported.len(), input_fn, message);
+            return syn::Error::new(args.input_fn.span(), message).to_compile_error().into()
+        }
+    }
 
-    let imp = genimpl(&schema);
+    let (imp, _) = genimpl_with_options(&schema, &options);
     imp.into()
 }
@@ -0,0 +1,35 @@
+use std::path::Path;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+/// Overrides for what the generator would otherwise come up with, usually read from a JSON file such as
+///
+/// ```json
+/// {
+///     "names": { "AngleNumber0": "Degrees", "#/definitions/LogicalNot<Predicate>": "NotPredicate" },
+///     "types": { "#/definitions/Color": "crate::Color" },
+///     "skip": ["Dict<InlineDataset>"]
+/// }
+/// ```
+///
+/// `names` and `types` are keyed by the name a type would be generated with or by a JSON pointer to its schema.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Names to give generated types instead
+    pub names: IndexMap<String, String>,
+    /// Existing Rust types (paths such as `crate::Color`) to use instead of generating one
+    pub types: IndexMap<String, String>,
+    /// Definitions, by name or pointer, that aren't generated at all.  Anything that refers to them gets a
+    /// `serde_json::Value` instead.
+    pub skip: Vec<String>,
+}
+
+impl Config {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let config = std::fs::read_to_string(path)?;
+
+        serde_json::from_str(&config).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
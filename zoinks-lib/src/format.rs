@@ -0,0 +1,131 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::parser::Schema;
+
+/// What a schema file is written in.  JSON is always understood, YAML and JSON5 need the `yaml` and `json5` features.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    Yaml,
+    Json5,
+}
+
+impl Format {
+    /// The format a file's extension says it's in: `.yaml` and `.yml` are YAML, `.json5` JSON5 and anything else JSON
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path.as_ref().extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("yaml") | Some("yml") => Self::Yaml,
+            Some("json5") => Self::Json5,
+            _ => Self::Json,
+        }
+    }
+
+    /// Reads a schema written in this format
+    pub fn parse<'a>(&self, input: &'a str) -> Result<Schema<'a>, ParseError> {
+        self.read(input)
+    }
+
+    // Reads anything written in this format, such as the OpenAPI document a schema is part of
+    pub(crate) fn read<'a, T: Deserialize<'a>>(&self, input: &'a str) -> Result<T, ParseError> {
+        match self {
+            Self::Json => serde_json::from_str(input).map_err(|e| ParseError::new(e.to_string(), Some((e.line(), e.column())))),
+            Self::Yaml => read_yaml(input),
+            Self::Json5 => read_json5(input),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "json5" => Ok(Self::Json5),
+            _ => Err(format!("Unknown format {}, expected one of json, yaml or json5", format)),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json => f.write_str("JSON"),
+            Self::Yaml => f.write_str("YAML"),
+            Self::Json5 => f.write_str("JSON5"),
+        }
+    }
+}
+
+/// Why a schema couldn't be read, and where in the file (one-based) if the parser could tell
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl ParseError {
+    pub(crate) fn new(message: String, location: Option<(usize, usize)>) -> Self {
+        // serde_json and serde_norway tack the location onto the message
+        let message = match (location, message.rsplit_once(" at line ")) {
+            (Some(_), Some((message, _))) => message.to_string(),
+            _ => message,
+        };
+
+        Self {
+            message,
+            line: location.map(|(line, _)| line),
+            column: location.map(|(_, column)| column),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{} at line {} column {}", self.message, line, column),
+            _ => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(feature = "yaml")]
+fn read_yaml<'a, T: Deserialize<'a>>(input: &'a str) -> Result<T, ParseError> {
+    serde_norway::from_str(input).map_err(|e| {
+        let location = e.location().map(|location| (location.line(), location.column()));
+        ParseError::new(e.to_string(), location)
+    })
+}
+
+#[cfg(not(feature = "yaml"))]
+fn read_yaml<'a, T: Deserialize<'a>>(_input: &'a str) -> Result<T, ParseError> {
+    Err(ParseError::new(String::from("YAML schemas need zoinks to be built with the yaml feature"), None))
+}
+
+#[cfg(feature = "json5")]
+fn read_json5<'a, T: Deserialize<'a>>(input: &'a str) -> Result<T, ParseError> {
+    json5::from_str(input).map_err(|e| match e {
+        json5::Error::Message { msg, location } => {
+            // Syntax errors come with pest's picture of where they are, the last line of which says what was wrong
+            let message = match location.is_some() && msg.contains('\n') {
+                true => msg.lines().last().unwrap_or_default().trim().trim_start_matches("= ").to_string(),
+                false => msg,
+            };
+            ParseError::new(message, location.map(|location| (location.line, location.column)))
+        },
+    })
+}
+
+#[cfg(not(feature = "json5"))]
+fn read_json5<'a, T: Deserialize<'a>>(_input: &'a str) -> Result<T, ParseError> {
+    Err(ParseError::new(String::from("JSON5 schemas need zoinks to be built with the json5 feature"), None))
+}
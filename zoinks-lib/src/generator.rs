@@ -1,8 +1,8 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
-use std::iter::FromIterator;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use heck::{CamelCase, SnakeCase};
+use indexmap::IndexMap;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use regex::Regex;
@@ -11,36 +11,200 @@ use serde_json::Value as JsonValue;
 #[allow(unused)]
 use log::{info, error, warn, debug};
 
-use super::Schema;
+use super::{Config, Draft, Format, Schema};
 
 mod tokens;
 use tokens::*;
 
-type OutVec = Vec<RustItem>;
+/// Something in the schema the generator couldn't faithfully express
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The generated type it concerns, or for `unsupported_keywords` a JSON pointer to the keyword
+    pub location: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+// Everything descend produces: the items to emit and anything worth telling the user about
+#[derive(Default)]
+struct Output {
+    items: Vec<RustItem>,
+    diagnostics: Vec<Diagnostic>,
+    // `Option` aliases for nullable types and the type they wrap
+    nullable: IndexMap<String, String>,
+    overrides: Overrides,
+}
+
+impl Output {
+    fn push(&mut self, item: RustItem) {
+        self.items.push(item);
+    }
+
+    fn extend<I: IntoIterator<Item = RustItem>>(&mut self, items: I) {
+        self.items.extend(items);
+    }
+
+    // pub type {name} = Option<{inner}>;
+    fn push_nullable(&mut self, name: String, inner: String) {
+        self.items.push(RustItem::TypeAlias(name.clone(), format!("Option<{}>", inner)));
+        self.nullable.insert(name, inner);
+    }
+
+    // Optional properties of a nullable type can be absent or null, so keep the two apart rather than nesting options,
+    // and required ones mustn't be taken to be null when they're missing
+    fn distinguish_null(&mut self) {
+        let nullable = &self.nullable;
+        for item in self.items.iter_mut() {
+            if let RustItem::Struct(struct_decl) = item {
+                for field in struct_decl.fields.iter_mut() {
+                    field.nullable = nullable.get(&field.field_type).cloned();
+                }
+            }
+        }
+    }
+
+    fn diagnose<M: Into<String>>(&mut self, location: &str, message: M) {
+        let diagnostic = Diagnostic {
+            location: location.into(),
+            message: message.into(),
+        };
+        warn!("{}", diagnostic);
+        self.diagnostics.push(diagnostic);
+    }
+}
+
+// The config, with its pointers resolved against the schema
+#[derive(Default)]
+struct Overrides {
+    // Chosen names and existing types by generated name
+    names: HashMap<String, String>,
+    types: HashMap<String, String>,
+    // The same for pointers to schemas other than definitions, by location (see `descend`).  We only find out what
+    // those would be called once descend gets to them.
+    node_names: HashMap<String, String>,
+    node_types: HashMap<String, String>,
+    // Definitions left out, by name
+    skip: HashSet<String>,
+    // The config key behind each entry of `names` and `types`, and the generated names and locations whose existing
+    // type was used
+    name_keys: HashMap<String, String>,
+    type_keys: HashMap<String, String>,
+    used: HashSet<String>,
+}
 
-// TODO: Make this more generic
-fn is_boxed(type_name: &str, field_name: &str, prop_count: usize) -> bool {
-    // If we have a mega struct let's box the fields
-    if prop_count > 15 {
-        return true
+impl Overrides {
+    fn new(schema: &Schema, config: &Config, out: &mut Output) -> Self {
+        let mut overrides = Self::default();
+
+        let entries = [
+            (&config.names, &mut overrides.names, &mut overrides.name_keys, &mut overrides.node_names),
+            (&config.types, &mut overrides.types, &mut overrides.type_keys, &mut overrides.node_types),
+        ];
+        for (entries, by_name, keys, by_node) in entries {
+            for (key, value) in entries.iter() {
+                let name = if !key.starts_with('#') {
+                    key.clone()
+                } else if let Some(definition) = definition_of(key) {
+                    sanitize(&definition).to_camel_case()
+                } else if matches!(key.as_str(), "#" | "#/") {
+                    String::from("TopLevel")
+                } else {
+                    let location = canonical_pointer(key);
+                    match schema.pointer(&location) {
+                        Some(_) => {
+                            by_node.insert(location, value.clone());
+                        },
+                        None => out.diagnose(key, "Config refers to nothing in the schema, ignoring it"),
+                    }
+                    continue
+                };
+
+                by_name.insert(name.clone(), value.clone());
+                keys.insert(name, key.clone());
+            }
+        }
+
+        for key in config.skip.iter() {
+            let definition = match key.starts_with('#') {
+                true => definition_of(key),
+                false => Some(key.clone()),
+            };
+            match definition.filter(|definition| schema.definitions.contains_key(definition)) {
+                Some(definition) => {
+                    overrides.skip.insert(definition);
+                },
+                None => out.diagnose(key, "Only definitions can be skipped and there is no such definition, ignoring it"),
+            }
+        }
+
+        overrides
     }
 
-    //  Because of recursion
-    if type_name == "DerivedStream" && field_name == "stream" {
-        return true
+    // The existing type to use instead of generating one named `name()` for the schema at `location`, if there is one
+    fn existing_type<F: FnOnce() -> String>(&mut self, location: &str, name: F) -> Option<String> {
+        if self.node_names.is_empty() && self.node_types.is_empty() && self.types.is_empty() {
+            return None
+        }
+
+        // The same schema can be generated more than once, e.g. as part of each branch of an if, but one name can only
+        // go to one of them
+        let name = name();
+        if let Some(chosen) = self.node_names.remove(location) {
+            self.names.insert(name.clone(), chosen);
+        }
+
+        let (used, rust_type) = match self.node_types.get(location) {
+            Some(rust_type) => (location, rust_type),
+            None => (name.as_str(), self.types.get(&name)?),
+        };
+        let rust_type = rust_type.clone();
+        self.used.insert(used.to_string());
+        Some(rust_type)
     }
 
-    if type_name == "LogicalNotQltPredicateQgt" && field_name == "not" {
-        return true
+    // The config keys nothing was generated for, given the names of everything that was
+    fn unused(&self, generated: &HashSet<&str>) -> Vec<String> {
+        let names = self.name_keys.iter().filter(|(name, _)| !generated.contains(name.as_str())).map(|(_, key)| key);
+        let types = self.type_keys.iter().filter(|(name, _)| !self.used.contains(*name)).map(|(_, key)| key);
+        let node_types = self.node_types.keys().filter(|location| !self.used.contains(*location));
+
+        let mut unused = names.chain(types).chain(self.node_names.keys()).chain(node_types).cloned().collect::<Vec<_>>();
+        unused.sort();
+        unused
     }
 
-    if type_name == "NonLayerRepeatSpec" && field_name == "spec" {
-        return true
+    // What every generated name that the config changes becomes
+    fn renames(&self) -> HashMap<String, String> {
+        let mut renames = self.names.clone();
+        renames.extend(self.types.iter().map(|(name, rust_type)| (name.clone(), rust_type.clone())));
+        renames.extend(self.skip.iter().map(|definition| (sanitize(definition).to_camel_case(), String::from("serde_json::Value"))));
+        renames
     }
+}
 
-    return false
+// Fields of structs with a lot of them are boxed to keep the struct small.  Recursive types are boxed wherever they
+// hold themselves (see `box_recursive`).
+fn is_boxed(prop_count: usize) -> bool {
+    prop_count > 15
 }
 
+// The order variants of a multi-type schema are tried in; integer before number so whole numbers keep their type
+const TYPE_ORDER: &[&str] = &["boolean", "integer", "number", "string", "array", "object"];
+
+// Strict and reserved keywords that can't be used as identifiers
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "self", "static", "struct",
+    "super", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+    "while", "yield",
+];
+
 fn sanitize(before: &str) -> String {
     let out = before
         .replace("[]", "_Array_")
@@ -54,32 +218,152 @@ fn sanitize(before: &str) -> String {
         ;
     let re = Regex::new(r#"[\[\]"\-]"#).unwrap();
     match re.replace_all(&out, "_") {
-        Cow::Borrowed("as") => "reserved_as".into(),
-        Cow::Borrowed("type") => "reserved_type".into(),
-        Cow::Borrowed("box") => "reserved_box".into(),
+        Cow::Borrowed(keyword) if KEYWORDS.contains(&keyword) => format!("reserved_{}", keyword),
+        Cow::Borrowed("Self") => "reserved_self_type".into(),
         Cow::Borrowed("URI") => "UniformResourceIdiot".into(),
-        s @ _ => s.into(),
+        s => s.into(),
     }
 }
 
-fn descend(in_name: Cow<str>, schema: &Schema, out: &mut OutVec, root: bool) -> Option<String> {
+// `location` is the JSON pointer to `schema`, which is what the config refers to schemas other than definitions by
+fn descend(in_name: Cow<str>, schema: &Schema, location: &str, out: &mut Output, root: bool) -> Option<String> {
+    if let Some(rust_type) = out.overrides.existing_type(location, || sanitize(&in_name).to_camel_case()) {
+        return Some(rust_type)
+    }
+
+    // false, or its older spelling {"not": {}}, and anything that has to match it too
+    if schema.is_nothing() || schema.all_of.iter().any(Schema::is_nothing) {
+        let name = sanitize(&in_name).to_camel_case();
+        out.push(RustItem::DeriveNoSerde);
+        out.push(RustItem::Never(name.clone()));
+        return Some(name)
+    }
+
+    let not = match schema.not.as_deref() {
+        Some(not) => not,
+        None => return descend_schema(in_name, schema, location, out, root),
+    };
+
+    let name = sanitize(&in_name).to_camel_case();
+
+    let config = match not_of(not) {
+        Some(config) => config,
+        None => {
+            out.diagnose(&name, "Unsupported not schema, it will not be enforced");
+            return descend_schema(in_name, schema, location, out, root)
+        }
+    };
+
+    // Plain values get wrapped directly, anything more involved gets a base type of its own
+    let instance_types = schema.instance_type.iter().map(String::as_str).collect::<Vec<_>>();
+    let plain = schema.properties.is_empty() && schema.any_of.is_empty() && schema.all_of.is_empty() && schema.one_of.is_empty()
+        && schema.enums.is_empty() && schema.constant.is_none() && schema.reference.is_none() && schema.if_schema.is_none()
+        && schema.minimum.is_none() && schema.maximum.is_none() && schema.exclusive_minimum.is_none() && schema.exclusive_maximum.is_none()
+        && schema.min_length.is_none() && schema.max_length.is_none();
+    let base_type = match (plain, instance_types.as_slice()) {
+        (true, []) => String::from("serde_json::Value"),
+        (true, ["string"]) => String::from("String"),
+        (true, ["number"]) => String::from("f64"),
+        (true, ["boolean"]) => String::from("bool"),
+        _ => descend_schema(Cow::Owned(format!("{}_base", name)), schema, location, out, false)?,
+    };
+
+    out.push(RustItem::DeriveNoSerde);
+    out.push(RustItem::NotValidator(name.clone(), base_type, config));
+
+    Some(name)
+}
+
+// What a not schema forbids, if it's simple enough to check: particular values or whole types.  Any keyword besides
+// type, enum and const (annotations aside) narrows what's forbidden, which these can't express.
+fn not_of(not: &Schema) -> Option<zoinks_support::NotValidatorConfig> {
+    let mut rest = not.constraints();
+    rest.instance_type.clear();
+    rest.enums.clear();
+    rest.constant = None;
+    if rest != Schema::default() {
+        return None
+    }
+
+    // A value is only forbidden if it's every one of const, enum and type that the not schema has
+    let listed = |value: &JsonValue| not.enums.is_empty() || not.enums.contains(value);
+    let typed = |value: &JsonValue| {
+        let value_type = crate::validator::type_of(value);
+        not.instance_type.is_empty() || not.instance_type.iter().any(|instance_type| instance_type == value_type || (instance_type == "number" && value_type == "integer"))
+    };
+    let config = match (not.constant.as_ref(), not.enums.is_empty(), not.instance_type.is_empty()) {
+        (Some(constant), _, _) => zoinks_support::NotValidatorConfig {
+            values: Some(constant).filter(|constant| listed(constant) && typed(constant)).iter().map(|value| value.to_string()).collect(),
+            types: vec![],
+        },
+        (None, false, _) => zoinks_support::NotValidatorConfig {
+            values: not.enums.iter().filter(|value| typed(value)).map(|value| value.to_string()).collect(),
+            types: vec![],
+        },
+        (None, true, false) => zoinks_support::NotValidatorConfig {
+            values: vec![],
+            types: not.instance_type.clone(),
+        },
+        (None, true, true) => return None,
+    };
+
+    Some(config)
+}
+
+fn descend_schema(in_name: Cow<str>, schema: &Schema, location: &str, out: &mut Output, root: bool) -> Option<String> {
     let name = sanitize(&in_name).to_camel_case();
 
-    let instance_types : HashSet<String> = HashSet::from_iter(schema.instance_type.iter().cloned());
+    let mut instance_types : BTreeSet<String> = schema.instance_type.iter().cloned().collect();
 
-    // let strings_nulls : HashSet<String> = HashSet::from_iter(["string".into(), "null".into()]);
+    // Properties only apply to objects and anything else is valid as it is, so an untyped schema with properties can
+    // be of any type, with a struct for objects.  An if/then/else about objects makes it a conditional struct instead.
+    if instance_types.is_empty() && !schema.properties.is_empty() && schema.reference.is_none() {
+        match schema.if_schema.is_some() && conditional_on_objects(schema, &instance_types) {
+            true => instance_types.insert("object".into()),
+            false => {
+                instance_types.extend(TYPE_ORDER.iter().filter(|t| **t != "integer").chain(&["null"]).map(|t| t.to_string()));
+                true
+            },
+        };
+    }
+
+    if schema.if_schema.is_some() && !conditional_on_objects(schema, &instance_types) {
+        out.diagnose(&name, "Unsupported if schema on something other than an object, it will not be enforced");
+    }
+
+    if !schema.any_of.is_empty() && instance_types.is_empty() {
+        // A null alternative makes the whole thing optional rather than being a variant of its own
+        let alternatives = schema.any_of.iter()
+            .enumerate()
+            .filter(|(_, any)| any.instance_type != ["null"])
+            .collect::<Vec<_>>();
+        let nullable = alternatives.len() < schema.any_of.len();
+
+        if let (true, [(i, any)]) = (nullable, alternatives.as_slice()) {
+            let child_name = format!("{}_{}_{}", name, any.instance_type.join("_"), i);
+            let inner = match descend(Cow::Borrowed(&child_name), any, &format!("{}/anyOf/{}", location, i), out, false) {
+                Some(inner) => sanitize(&inner),
+                None => sanitize(&child_name).to_camel_case(),
+            };
+            out.push_nullable(name.clone(), inner);
+            return Some(name);
+        }
+
+        let enum_name = match nullable {
+            true => format!("{}Value", name),
+            false => name.clone(),
+        };
 
-    if schema.any_of.len() > 0 && instance_types.is_empty() {
         let mut outer = Vec::with_capacity(4);
-        outer.push(RustItem::DocComment(format!("any_of enum: {}", name)));
+        outer.push(RustItem::DocComment(format!("any_of enum: {}", enum_name)));
         outer.push(RustItem::DeriveCommon);
         outer.push(RustItem::SerdeUntagged);
 
-        let mut variants = Vec::with_capacity(schema.any_of.len());
+        let mut variants = Vec::with_capacity(alternatives.len());
 
-        for (i, any) in schema.any_of.iter().enumerate() {
+        for (i, any) in alternatives {
             let child_name = format!("{}_{}_{}", name, any.instance_type.join("_"), i);
-            match descend(Cow::Owned(child_name), any, out, false) {
+            match descend(Cow::Owned(child_name), any, &format!("{}/anyOf/{}", location, i), out, false) {
                 Some(variant) => {
                     let variant = sanitize(&variant);
                     variants.push(EnumVariant::Tuple(variant.clone(), variant));
@@ -88,221 +372,1042 @@ fn descend(in_name: Cow<str>, schema: &Schema, out: &mut OutVec, root: bool) ->
             }
         }
         outer.push(RustItem::Enum(Enum {
-            name: name.clone(),
+            name: enum_name.clone(),
             variants,
         }));
         out.extend(outer);
-        return Some(name)
-    } else if schema.enums.len() > 0 {
-        let allow_string = instance_types.contains("string");
-        let allow_number = instance_types.contains("number");
-        let allow_null = instance_types.contains("null");
-
-        let variants = schema.enums
-            .iter()
-            .filter_map(|e| {
-                match e {
-                    JsonValue::String(s) => {
-                        if allow_string {
-                            Some((s.into(), s.to_camel_case()))
-                        } else {
-                            warn!("Got a string enum value, but string values not allowed: {:?}", s.to_camel_case());
-                            None
-                        }
-                    }
-                    JsonValue::Number(n) => {
-                        if allow_number {
-                            Some((format!("{}", n), format!("{}_{}", name, n).to_camel_case()))
-                        } else {
-                            warn!("Got a numeric enum value, but number values not allowed: {:?}", n);
-                            None
-                        }
-                    },
-                    JsonValue::Null => {
-                        if allow_null {
-                            Some((String::from("null"), String::from("Null")))
-                        } else {
-                            warn!("Got a null enum value, but null values not allowed");
-                            None
-                        }
-                    },
-                    _ => todo!("unsupported enum type: {:?}", e)
-                }
+
+        if nullable {
+            out.push_nullable(name.clone(), enum_name);
+        }
+        Some(name)
+    } else if !schema.enums.is_empty() {
+        // The members themselves say what kinds of value are allowed, a type only narrows them down
+        let mut members : Vec<&JsonValue> = Vec::with_capacity(schema.enums.len());
+        for member in schema.enums.iter() {
+            if !instance_types.is_empty() && !instance_types.iter().any(|instance_type| zoinks_support::is_instance_of(member, instance_type)) {
+                out.diagnose(&name, format!("Enum value {} is not a {}, dropping it", member, schema.instance_type.join(" or ")));
+            } else if members.iter().any(|existing| zoinks_support::json_eq(existing, member)) {
+                out.diagnose(&name, format!("Enum value {} is a duplicate, dropping it", member));
+            } else {
+                members.push(member);
+            }
+        }
+
+        let mut taken = HashSet::new();
+        let variants = members.iter()
+            .enumerate()
+            .map(|(i, member)| {
+                (unique_name(&mut taken, variant_name(member, i)), (*member).clone())
             })
-            .map(|(old_name, new_name)| EnumVariant::Unit(new_name, old_name))
             .collect::<Vec<_>>();
 
-        out.push(RustItem::DeriveCommon);
-        out.push(RustItem::Enum(Enum {
-            name: name.clone(),
-            variants,
-        }));
+        // serde can only rename unit variants to strings, anything else needs matching by value
+        if members.iter().all(|member| member.is_string()) {
+            let variants = variants.into_iter()
+                .map(|(variant, member)| match member {
+                    JsonValue::String(s) => EnumVariant::Unit(variant, s),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>();
 
-        return Some(name)
+            out.push(RustItem::DeriveCommon);
+            out.push(RustItem::Enum(Enum {
+                name: name.clone(),
+                variants,
+            }));
+        } else {
+            out.push(RustItem::DeriveNoSerde);
+            out.push(RustItem::LiteralEnum(LiteralEnum {
+                name: name.clone(),
+                variants,
+            }));
+        }
+
+        Some(name)
+    } else if schema.if_schema.is_some() && conditional_on_objects(schema, &instance_types) {
+        Some(descend_conditional(&name, schema, location, out))
     } else if instance_types.len() == 1 && instance_types.contains("object") {
         if schema.properties.is_empty() {
             out.push(RustItem::DeriveCommon);
             out.push(RustItem::TupleStruct(name.clone(), "serde_json::Value".into()));
 
-            return Some(name)
+            Some(name)
         } else {
-            let mut outer = Vec::with_capacity(4);
-            outer.push(RustItem::DeriveCommon);
-
-            let prop_count = schema.properties.len();
-            let mut fields = Vec::with_capacity(prop_count);
-
-            for (prop_name, prop_schema) in schema.properties.iter() {
-                let prop_type = descend(Cow::Owned(format!("{}_prpty_{}", name, prop_name).to_camel_case()), &prop_schema, out, false).expect(&format!("Failed to determine type for {}::{}", name,prop_name));
-
-                let field_name = sanitize(prop_name).to_snake_case();
-
-                fields.push(StructField {
-                    old_name: prop_name.to_string(),
-                    field_type: prop_type,
-                    required: false,
-                    boxed: is_boxed(&name, &field_name, prop_count),
-                    name: field_name,
-                    description: prop_schema.description.as_ref().map(|x| x.to_string()),
-                });
-            }
+            let object = descend_object(&name, &[(schema, location.into())], false, out);
 
-            let fields = fields.into_iter()
-                .map(|field| {
-                    if schema.required.contains(&field.old_name) {
-                        StructField {
-                            required: true,
-                            ..field
-                        }
-                    } else {
-                        field
-                    }
-                })
-                .collect();
-
-            let additional_fields = match schema.additional_properties.as_ref() {
-                None => true,
-                Some(additional_properties) => match additional_properties {
-                    crate::parser::AdditionalProperties::Boolean(b) if *b == false => false,
-                    _ => true
-                }
-            };
-
-            outer.push(RustItem::Struct(Struct {
-                name: name.clone(),
-                fields,
-                additional_fields,
-            }));
-
-            out.extend(outer);
-            return Some(name)
+            out.push(RustItem::DeriveCommon);
+            out.push(RustItem::Struct(object));
+            Some(name)
         }
-    } else if schema.reference.is_some() && instance_types.is_empty() {
-        let reference = schema.reference.as_ref().unwrap();
-
-        if reference.starts_with("#/definitions/") {
-            let reference = &reference[14..];
-            let reference = sanitize(reference).to_camel_case();
+    } else if let (Some(reference), true) = (schema.reference.as_ref(), instance_types.is_empty()) {
+        // The schema itself is TopLevel, recursive schemas refer to it
+        let target = match reference.as_ref() {
+            "#" => Some(String::from("top_level")),
+            reference => definition_of(reference),
+        };
+        if let Some(reference) = target {
+            let reference = sanitize(&reference).to_camel_case();
             if root {
                 out.push(RustItem::DeriveCommon);
                 out.push(RustItem::TupleStruct(name, reference.clone()));
                 // out.push(format!("pub struct {}({});", name, reference))
             }
 
-            return Some(reference)
+            Some(reference)
         } else {
-            error!("Unsupported reference: {}", reference);
-            return Some(String::from("serde_json::Value"))
+            out.diagnose(&name, format!("Unsupported reference {}, using serde_json::Value", reference));
+            Some(String::from("serde_json::Value"))
         }
     } else if instance_types.len() == 1 {
         if instance_types.contains("number") {
-            if schema.minimum.is_some() || schema.exclusive_minimum.is_some() || schema.maximum.is_some() || schema.exclusive_maximum.is_some() {
-                out.push(RustItem::NumericValidator(name.clone(), zoinks_support::NumericValidatorConfig {
-                    min: schema.minimum,
-                    exclusive_min: schema.exclusive_minimum,
-                    max: schema.maximum,
-                    exclusive_max: schema.exclusive_maximum,
-                }));
+            if let Some(config) = bounds_of(schema) {
                 out.push(RustItem::DeriveNoSerde);
+                out.push(RustItem::NumericValidator(name.clone(), config));
             } else {
                 out.push(RustItem::DeriveCommon);
+                out.push(RustItem::TupleStruct(name.clone(), String::from("f64")));
             }
-            out.push(RustItem::TupleStruct(name.clone(), String::from("f64")));
 
-            return Some(name)
+            Some(name)
+        } else if instance_types.contains("integer") {
+            // Even without bounds, since serde's i64 turns away integers written like 1.0
+            let config = bounds_of(schema).unwrap_or_default();
+            out.push(RustItem::DeriveNoSerde);
+            out.push(RustItem::IntegerValidator(name.clone(), zoinks_support::IntegerValidatorConfig(config)));
+
+            Some(name)
         } else if instance_types.contains("boolean") {
             out.push(RustItem::DeriveCommon);
             out.push(RustItem::TupleStruct(name.clone(), String::from("bool")));
 
-            return Some(name)
+            Some(name)
         } else if instance_types.contains("string") {
             if schema.min_length.is_some() || schema.max_length.is_some() {
+                out.push(RustItem::DeriveNoSerde);
                 out.push(RustItem::StringValidator(name.clone(), zoinks_support::StringValidatorConfig {
                     min_length: schema.min_length,
                     max_length: schema.max_length,
                 }));
-                out.push(RustItem::DeriveNoSerde);
             } else {
                 out.push(RustItem::DeriveCommon);
+                out.push(RustItem::TupleStruct(name.clone(), String::from("String")));
             }
-            out.push(RustItem::TupleStruct(name.clone(), String::from("String")));
 
-            return Some(name)
+            Some(name)
         } else if instance_types.contains("null") {
-            return Some(String::from("Null"))
+            Some(String::from("Null"))
+        } else if instance_types.contains("array") && !schema.prefix_items.is_empty() {
+            let len = schema.prefix_items.len();
+
+            // A Rust tuple has exactly as many items as it has types, and zoinks_support validates up to 12 of them
+            if schema.min_items == Some(len as u32) && schema.max_items == Some(len as u32) && len <= 12 {
+                let mut item_types = Vec::with_capacity(len);
+                for (i, item) in schema.prefix_items.iter().enumerate() {
+                    let item_name = format!("{}Item{}", name.to_camel_case(), i);
+                    item_types.push(match descend(Cow::Borrowed(&item_name), item, &format!("{}/prefixItems/{}", location, i), out, false) {
+                        Some(name) => sanitize(&name),
+                        None => sanitize(&item_name),
+                    });
+                }
+
+                out.push(RustItem::TypeAlias(name.clone(), format!("({},)", item_types.join(", "))));
+            } else {
+                out.diagnose(&name, "Only tuples with a fixed number of items are supported, using Vec<serde_json::Value>");
+                out.push(RustItem::TypeAlias(name.clone(), String::from("Vec<serde_json::Value>")));
+            }
+
+            Some(name)
         } else if instance_types.contains("array") {
-            let inner_name = format!("{}Item", name.to_camel_case());
-            let inner_name = match descend(Cow::Borrowed(&inner_name), schema.items.as_ref().unwrap(), out, false) {
-                Some(name) => name,
-                None => inner_name,
+            let inner_name = match schema.items.as_ref() {
+                Some(items) => {
+                    let inner_name = format!("{}Item", name.to_camel_case());
+                    match descend(Cow::Borrowed(&inner_name), items, &format!("{}/items", location), out, false) {
+                        Some(name) => sanitize(&name),
+                        None => sanitize(&inner_name),
+                    }
+                },
+                None => String::from("serde_json::Value"),
             };
 
-            out.push(RustItem::TypeAlias(name.clone(), format!("Vec<{}>", sanitize(&inner_name))));
+            out.push(RustItem::TypeAlias(name.clone(), format!("Vec<{}>", inner_name)));
 
-            return Some(name)
+            Some(name)
         } else {
-            todo!()
+            out.diagnose(&name, format!("Unsupported type {}, using serde_json::Value", schema.instance_type.join(" or ")));
+            Some(String::from("serde_json::Value"))
         }
     } else if instance_types.len() > 1 {
-        let variants = instance_types.iter()
-            .map(|e| match e.as_ref() {
-                "number" => EnumVariant::Tuple(String::from("Number"), String::from("f64")),
-                "string" => EnumVariant::Tuple(String::from("String"), String::from("String")),
-                "boolean" => EnumVariant::Tuple(String::from("Boolean"), String::from("bool")),
-                "null" => EnumVariant::Unit(String::from("Null"), String::from("null")),
-                _ => unimplemented!(),
-            })
-            .collect::<Vec<_>>();
+        // Each type gets its own variant carrying the constraints that apply to it.  Untagged enums try variants
+        // in order, so the order is fixed rather than whatever the schema (or a hash set) happens to give.
+        let nullable = instance_types.contains("null");
+        let mut variants = Vec::with_capacity(instance_types.len());
+
+        for instance_type in TYPE_ORDER.iter().filter(|t| instance_types.contains(**t)) {
+            let mut variant_schema = schema.clone();
+            variant_schema.instance_type = vec![String::from(*instance_type)];
+
+            let variant_name = format!("{}_{}", name, instance_type);
+            let variant_type = match descend(Cow::Borrowed(&variant_name), &variant_schema, location, out, false) {
+                Some(variant_type) => sanitize(&variant_type),
+                None => sanitize(&variant_name),
+            };
+            variants.push(EnumVariant::Tuple(instance_type.to_camel_case(), variant_type));
+        }
+
+        if let (true, [EnumVariant::Tuple(_, only)]) = (nullable, variants.as_slice()) {
+            out.push_nullable(name.clone(), only.clone());
+            return Some(name);
+        }
+
+        let enum_name = match nullable {
+            true => format!("{}Value", name),
+            false => name.clone(),
+        };
 
         out.push(RustItem::DeriveCommon);
+        out.push(RustItem::SerdeUntagged);
         out.push(RustItem::Enum(Enum {
-            name: name.clone(), variants
+            name: enum_name.clone(), variants
         }));
-        // out.push(format!("pub enum {} {{ {} }}", name, enums));
-        return Some(name)
+
+        if nullable {
+            out.push_nullable(name.clone(), enum_name);
+        }
+        Some(name)
     } else {
         warn!("Empty struct? {}", name);
         out.push(RustItem::DeriveCommon);
         // out.push(RustItem::UnitStruct(name.clone()));
         out.push(RustItem::TupleStruct(name.clone(), String::from("serde_json::Value")));
 
-        return Some(name)
+        Some(name)
+    }
+}
+
+// Bounds for a number or integer schema, if it has any
+fn bounds_of(schema: &Schema) -> Option<zoinks_support::NumericValidatorConfig> {
+    if schema.minimum.is_none() && schema.exclusive_minimum.is_none() && schema.maximum.is_none() && schema.exclusive_maximum.is_none() {
+        return None;
+    }
+
+    Some(zoinks_support::NumericValidatorConfig {
+        min: schema.minimum,
+        exclusive_min: schema.exclusive_minimum,
+        max: schema.maximum,
+        exclusive_max: schema.exclusive_maximum,
+    })
+}
+
+// Builds a struct from the properties of several schemas, each with its location, layered on top of `layers[0]`.  If
+// `union` is set the other layers are alternatives, so only the base schema's required properties are required.
+fn descend_object(name: &str, layers: &[(&Schema, Cow<str>)], union: bool, out: &mut Output) -> Struct {
+    let (schema, location) = (layers[0].0, &layers[0].1);
+
+    // Later layers refine earlier ones.  In a union the base wins, and a property that only the alternatives define
+    // (differently) could be either, so it's left as a plain JSON value.
+    let mut properties : IndexMap<&str, Option<(&Schema, String)>> = IndexMap::new();
+    for (layer, layer_location) in layers.iter() {
+        for (prop_name, prop_schema) in layer.properties.iter() {
+            let prop = (prop_schema, format!("{}/properties/{}", layer_location, pointer_token(prop_name)));
+            match properties.get_mut(prop_name.as_ref()) {
+                Some(_) if union && schema.properties.contains_key(prop_name) => {},
+                Some(existing) if union => *existing = None,
+                Some(existing) => *existing = Some(prop),
+                None => {
+                    properties.insert(prop_name, Some(prop));
+                },
+            }
+        }
+    }
+
+    let required = match union {
+        true => &layers[..1],
+        false => layers,
+    };
+
+    let prop_count = properties.len();
+    let mut fields = Vec::with_capacity(prop_count);
+
+    for (prop_name, prop_schema) in properties.into_iter() {
+        let prop_type = match prop_schema.as_ref() {
+            Some((prop_schema, prop_location)) => descend(Cow::Owned(format!("{}_prpty_{}", name, prop_name).to_camel_case()), prop_schema, prop_location, out, false).unwrap_or_else(|| panic!("Failed to determine type for {}::{}", name,prop_name)),
+            None => String::from("serde_json::Value"),
+        };
+
+        let field_name = sanitize(prop_name).to_snake_case();
+
+        fields.push(StructField {
+            old_name: prop_name.to_string(),
+            field_type: prop_type,
+            required: required.iter().any(|(layer, _)| layer.required.iter().any(|r| r == prop_name)),
+            boxed: is_boxed(prop_count),
+            nullable: None,
+            name: field_name,
+            description: prop_schema.and_then(|(prop_schema, _)| prop_schema.description.as_ref()).map(|x| x.to_string()),
+        });
+    }
+
+    // A dependent schema's required list is just more dependentRequired, anything else needs a type of its own
+    let mut dependent_required = schema.dependent_required.iter()
+        .map(|(trigger, dependents)| (trigger.clone(), dependents.clone()))
+        .collect::<Vec<_>>();
+    let mut dependent_schemas = Vec::with_capacity(schema.dependent_schemas.len());
+
+    for (trigger, dependent_schema) in schema.dependent_schemas.iter() {
+        if !dependent_schema.required.is_empty() {
+            dependent_required.push((trigger.clone(), dependent_schema.required.clone()));
+        }
+
+        if !dependent_schema.properties.is_empty() {
+            let dependent_name = format!("{}_dpndnt_{}", name, trigger).to_camel_case();
+            let dependent_location = format!("{}/dependentSchemas/{}", location, pointer_token(trigger));
+            match descend(Cow::Owned(dependent_name), dependent_schema, &dependent_location, out, false) {
+                Some(dependent_type) => dependent_schemas.push((trigger.clone(), dependent_type)),
+                None => error!("Invalid dependent schema: {}::{}", name, trigger),
+            }
+        }
+    }
+
+    let additional_fields = !matches!(schema.additional_properties, Some(crate::parser::AdditionalProperties::Boolean(false)));
+
+    Struct {
+        name: name.into(),
+        fields,
+        additional_fields,
+        min_properties: schema.min_properties,
+        max_properties: schema.max_properties,
+        dependent_required,
+        dependent_schemas,
+        conditional: None,
+    }
+}
+
+// The part of an if schema we can evaluate at runtime: const/enum property values and required properties
+fn condition_of(if_schema: &Schema) -> Option<Condition> {
+    if !if_schema.any_of.is_empty() || !if_schema.all_of.is_empty() || !if_schema.one_of.is_empty() || if_schema.not.is_some() || if_schema.reference.is_some() || if_schema.if_schema.is_some() {
+        return None
+    }
+
+    if if_schema.properties.is_empty() && if_schema.required.is_empty() {
+        return None
+    }
+
+    let mut properties = Vec::with_capacity(if_schema.properties.len());
+    for (prop_name, prop_schema) in if_schema.properties.iter() {
+        let values = match (prop_schema.constant.as_ref(), prop_schema.enums.is_empty()) {
+            (Some(constant), _) => vec![constant.to_string()],
+            (None, false) => prop_schema.enums.iter().map(|value| value.to_string()).collect(),
+            (None, true) => return None,
+        };
+        properties.push((prop_name.to_string(), values));
+    }
+
+    Some(Condition {
+        properties,
+        required: if_schema.required.clone(),
+    })
+}
+
+// A condition testing a single property against a single value, e.g. {"properties": {"kind": {"const": "circle"}}}
+fn discriminator_of(condition: &Condition) -> Option<(&str, &str)> {
+    match condition.properties.as_slice() {
+        [(property, values)] if values.len() == 1 => Some((property, &values[0])),
+        _ => None,
     }
 }
 
+// A readable variant name for an enum or const value, `Value{i}` when there's nothing better
+fn variant_name(value: &JsonValue, i: usize) -> String {
+    match value {
+        JsonValue::String(s) if s.chars().any(|c| c.is_alphabetic()) => {
+            let variant = sanitize(s).to_camel_case();
+            match variant.starts_with(|c: char| c.is_ascii_digit()) {
+                true => format!("N{}", variant),
+                false => variant,
+            }
+        },
+        JsonValue::Bool(true) => String::from("True"),
+        JsonValue::Bool(false) => String::from("False"),
+        JsonValue::Null => String::from("Null"),
+        JsonValue::Number(n) => number_variant_name(n.as_f64().unwrap_or_default()),
+        JsonValue::Array(_) => format!("Array{}", i),
+        JsonValue::Object(_) => format!("Object{}", i),
+        _ => format!("Value{}", i),
+    }
+}
+
+// Names a number by its value, so 1 and 1.0 are both N1, -2 is Neg2 and 0.5 is N0_5
+fn number_variant_name(n: f64) -> String {
+    let digits = match n.fract() == 0.0 && n.abs() < 1e15 {
+        true => format!("{}", n.abs() as i64),
+        false => format!("{}", n.abs()).replace('.', "_"),
+    };
+
+    match n.is_sign_negative() && n != 0.0 {
+        true => format!("Neg{}", digits),
+        false => format!("N{}", digits),
+    }
+}
+
+// Appends a counter to `name` until it no longer collides with anything in `taken`
+fn unique_name(taken: &mut HashSet<String>, name: String) -> String {
+    let mut unique = name.clone();
+    let mut i = 1;
+    while taken.contains(&unique) {
+        unique = format!("{}{}", name, i);
+        i += 1;
+    }
+    taken.insert(unique.clone());
+    unique
+}
+
+// Whether an if/then/else can become structs: the schema is an object, or says nothing about its type and its
+// branches are about objects.  Anything else is left to the rest of the schema.
+fn conditional_on_objects(schema: &Schema, instance_types: &BTreeSet<String>) -> bool {
+    fn about_objects(schema: &Schema) -> bool {
+        !schema.properties.is_empty() || !schema.required.is_empty() || schema.additional_properties.is_some()
+            || schema.min_properties.is_some() || schema.max_properties.is_some() || !schema.dependent_required.is_empty()
+            || !schema.dependent_schemas.is_empty() || !schema.pattern_properties.is_empty()
+            || [&schema.if_schema, &schema.then_schema, &schema.else_schema].iter().any(|branch| branch.as_deref().is_some_and(about_objects))
+    }
+
+    match instance_types.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["object"] => true,
+        [] => about_objects(schema),
+        _ => false,
+    }
+}
+
+// Whether descend has nothing to go on (no type, properties, reference, anyOf or enum, and it isn't false) and leaves
+// the schema a plain serde_json::Value, so that none of its keywords are enforced
+pub(crate) fn untyped_value(schema: &Schema) -> bool {
+    !schema.is_nothing() && !schema.all_of.iter().any(Schema::is_nothing) && schema.instance_type.is_empty() && schema.properties.is_empty() && schema.any_of.is_empty() && schema.enums.is_empty()
+        && schema.reference.is_none() && schema.dynamic_reference.is_none() && schema.recursive_reference.is_none()
+        && !(schema.if_schema.is_some() && conditional_on_objects(schema, &BTreeSet::new()))
+}
+
+// if/then/else.  When the if tests a single property against a constant (including if/then/else if... chains on the
+// same property) each branch becomes an enum variant, otherwise we emit the union of every branch's properties and
+// check the branch when deserializing.
+fn descend_conditional(name: &str, schema: &Schema, location: &str, out: &mut Output) -> String {
+    let empty = Schema::default();
+    let here = |subschema: &str| Cow::Owned(format!("{}/{}", location, subschema));
+
+    let mut branches = Vec::new();
+    let mut discriminator = None;
+    let mut current = Some(schema);
+    let mut current_location = location.to_string();
+
+    while let Some(if_schema) = current.and_then(|current| current.if_schema.as_ref()) {
+        let condition = match condition_of(if_schema) {
+            Some(condition) => condition,
+            None => break,
+        };
+
+        match (discriminator_of(&condition), discriminator.as_ref()) {
+            (Some((property, _)), None) => discriminator = Some(property.to_string()),
+            (Some((property, _)), Some(discriminator)) if property == discriminator => {},
+            _ => break,
+        }
+
+        branches.push((condition, current.unwrap().then_schema.as_deref(), format!("{}/then", current_location)));
+        current = current.unwrap().else_schema.as_deref();
+        current_location.push_str("/else");
+    }
+
+    if branches.is_empty() {
+        // Not a discriminator, fall back to a struct with the union of every branch's properties
+        let condition = match schema.if_schema.as_deref().and_then(condition_of) {
+            Some(condition) => condition,
+            None => {
+                out.diagnose(name, "Unsupported if schema, branches will not be enforced");
+                let layers = vec![(Some(schema), location.into()), (schema.then_schema.as_deref(), here("then")), (schema.else_schema.as_deref(), here("else"))];
+                let layers = layers.into_iter().filter_map(|(layer, location)| Some((layer?, location))).collect::<Vec<_>>();
+                let object = descend_object(name, &layers, true, out);
+                out.push(RustItem::DeriveCommon);
+                out.push(RustItem::Struct(object));
+                return name.into()
+            }
+        };
+
+        let mut branch_type = |suffix: &str, branch: Option<&Schema>| {
+            let branch = branch?;
+            let branch_name = format!("{}_{}", name, suffix).to_camel_case();
+            let object = descend_object(&branch_name, &[(schema, location.into()), (branch, here(suffix))], false, out);
+            out.push(RustItem::DeriveCommon);
+            out.push(RustItem::Struct(object));
+            Some(branch_name)
+        };
+        let then_type = branch_type("then", schema.then_schema.as_deref());
+        let else_type = branch_type("else", schema.else_schema.as_deref());
+
+        let layers = vec![(Some(schema), location.into()), (schema.then_schema.as_deref(), here("then")), (schema.else_schema.as_deref(), here("else"))];
+        let layers = layers.into_iter().filter_map(|(layer, location)| Some((layer?, location))).collect::<Vec<_>>();
+        let mut object = descend_object(name, &layers, true, out);
+        object.conditional = Some(Conditional {
+            condition,
+            then_type,
+            else_type,
+        });
+
+        out.push(RustItem::DeriveCommon);
+        out.push(RustItem::Struct(object));
+        return name.into()
+    }
+
+    // A branch that's only a reference (or false) on a schema with nothing else to add is the variant's type as it is,
+    // as with the tagged unions OpenAPI's discriminator describes
+    let standalone = |branch: &Schema| schema.properties.is_empty()
+        && (branch.is_nothing() || (branch.reference.is_some() && branch.properties.is_empty()));
+    let branch_type = |variant_type: String, branch: Option<&Schema>, branch_location: &str, out: &mut Output| match branch.filter(|branch| standalone(branch)) {
+        Some(branch) => descend(Cow::Borrowed(&variant_type), branch, branch_location, out, false).unwrap_or(variant_type),
+        None => {
+            let layers = [(schema, location.into()), (branch.unwrap_or(&empty), Cow::Borrowed(branch_location))];
+            let object = descend_object(&variant_type, &layers, false, out);
+            out.push(RustItem::DeriveCommon);
+            out.push(RustItem::Struct(object));
+            variant_type
+        },
+    };
+
+    let mut variants = Vec::with_capacity(branches.len());
+    for (i, (condition, then_schema, then_location)) in branches.into_iter().enumerate() {
+        let (_, value) = discriminator_of(&condition).unwrap();
+        let variant = variant_name(&serde_json::from_str(value).unwrap_or(JsonValue::Null), i);
+        let variant_type = branch_type(format!("{}_{}", name, variant).to_camel_case(), then_schema, &then_location, out);
+        variants.push((condition, variant, variant_type));
+    }
+
+    if current.is_some_and(|current| current.if_schema.is_some()) {
+        out.diagnose(name, "Nested if schema is not a discriminator on the same property, it will not be enforced");
+    }
+
+    // An else that nothing is valid against would be a variant that can't be constructed
+    let otherwise = match variants.iter().any(|(_, variant, _)| variant == "Other") {
+        true => String::from("Otherwise"),
+        false => String::from("Other"),
+    };
+    let otherwise = match current.filter(|current| current.is_nothing()) {
+        Some(_) => None,
+        None => Some((otherwise.clone(), branch_type(format!("{}_{}", name, otherwise).to_camel_case(), current, &current_location, out))),
+    };
+
+    out.push(RustItem::DocComment(format!("if/then/else enum: {}", name)));
+    out.push(RustItem::DeriveNoSerde);
+    out.push(RustItem::ConditionalEnum(ConditionalEnum {
+        name: name.into(),
+        branches: variants,
+        otherwise,
+    }));
+
+    name.into()
+}
+
 pub fn genimpl(schema: &Schema) -> TokenStream2 {
-    let mut out : OutVec = vec![
-        RustItem::DeriveCommon,
-        RustItem::UnitStruct(String::from("Null")),
-    ];
+    genimpl_with_diagnostics(schema).0
+}
+
+/// Like `genimpl`, but also returns everything in the schema that couldn't be faithfully expressed
+pub fn genimpl_with_diagnostics(schema: &Schema) -> (TokenStream2, Vec<Diagnostic>) {
+    genimpl_with_options(schema, &Options::default())
+}
+
+/// Like `genimpl_with_diagnostics`, with control over what gets generated
+pub fn genimpl_with_options(schema: &Schema, options: &Options) -> (TokenStream2, Vec<Diagnostic>) {
+    let schema = as_draft(schema, options.draft);
+    let (sections, diagnostics, _) = generate(&schema, options);
+
+    let items = sections.iter().flat_map(|(_, items)| items.iter());
+    let tokens = quote! {
+        #(#items)*
+    };
+
+    (tokens, diagnostics)
+}
+
+/// What to generate and how to lay it out
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Only generate the types reachable from these definitions, given by name (`TopLevelSpec`) or JSON pointer
+    /// (`#/definitions/TopLevelSpec`, or `#` for the schema itself).  Everything is generated if this is empty.
+    pub roots: Vec<String>,
+    /// Which definitions share a module, see `genmodules`
+    pub grouping: Grouping,
+    /// What happens to types that are identical apart from their names
+    pub merge: Merge,
+    /// Names, existing types and definitions to leave out
+    pub config: Config,
+    /// The draft to read the schema as, instead of the one `$schema` names.  Schemas that don't name one are read as
+    /// 2020-12.
+    pub draft: Option<Draft>,
+    /// What schema files are written in, instead of going by their extension (see `Format::from_path`)
+    pub format: Option<Format>,
+    /// Reads schema files as OpenAPI 3.0 or 3.1 documents (see `from_openapi`), generating types for their
+    /// `components/schemas`.  There's no `TopLevel` then unless `roots` asks for `#`.
+    pub openapi: bool,
+    /// Refuses to generate anything for schemas with keywords the generated types won't enforce (see
+    /// `unsupported_keywords`).  `schema2struct!` fails to compile then and `schemafile2dir` returns an error.
+    pub strict: bool,
+}
+
+/// What happens to generated types that are identical apart from their names, such as the many properties that are
+/// all `{"type": "number", "minimum": 0, "maximum": 1}`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Merge {
+    /// Every type is kept
+    #[default]
+    Never,
+    /// One type is used for all of them, named after a definition if one of them is one, otherwise the shortest name.
+    /// Definitions are never merged into each other.
+    Identical,
+    /// Like `Identical`, but the other names are kept as type aliases
+    IdenticalWithAliases,
+}
+
+/// How `genmodules` assigns definitions to modules
+#[derive(Debug, Clone, Default)]
+pub enum Grouping {
+    /// A module for each definition
+    #[default]
+    Definition,
+    /// Definitions whose names start with the same word, e.g. `AxisConfig` and `AxisOrient`, share a module
+    FirstWord,
+    /// Maps a definition name to the name of its module
+    Custom(fn(&str) -> String),
+}
+
+impl Grouping {
+    fn module_name(&self, definition: &str) -> String {
+        let name = match self {
+            Self::Definition => sanitize(definition).to_snake_case(),
+            Self::FirstWord => sanitize(definition).to_camel_case().to_snake_case().split('_').next().unwrap_or_default().to_string(),
+            Self::Custom(rule) => rule(definition).to_snake_case(),
+        };
+
+        // The module name must itself be a valid identifier
+        sanitize(&name)
+    }
+}
+
+/// Generated code split into modules, see `genmodules`
+pub struct Modules {
+    /// Items that don't belong to any definition, such as the top level schema
+    pub root: TokenStream2,
+    /// Module name and items, in name order
+    pub modules: Vec<(String, TokenStream2)>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// How many types were merged into an identical one, see `Options::merge`
+    pub merged: usize,
+}
+
+/// Like `genimpl_with_diagnostics`, but puts the types generated for each definition in a module chosen by
+/// `options.grouping`.  The modules refer to each other's types through their parent, so it should glob import them
+/// all.
+pub fn genmodules(schema: &Schema, options: &Options) -> Modules {
+    let schema = as_draft(schema, options.draft);
+    let (sections, diagnostics, merged) = generate(&schema, options);
+
+    let mut root = Vec::new();
+    let mut modules : IndexMap<String, Vec<RustItem>> = IndexMap::new();
+    for (definition, items) in sections {
+        match definition {
+            Some(definition) => modules.entry(options.grouping.module_name(definition)).or_default().extend(items),
+            None => root.extend(items),
+        }
+    }
+    modules.sort_keys();
+
+    Modules {
+        root: quote! { #(#root)* },
+        modules: modules.into_iter().map(|(name, items)| (name, quote! { #(#items)* })).collect(),
+        diagnostics,
+        merged,
+    }
+}
+
+// The schema the way `draft` (or failing that `$schema`) reads it, with every reference a JSON pointer
+fn as_draft<'s, 'a>(schema: &'s Schema<'a>, draft: Option<Draft>) -> Cow<'s, Schema<'a>> {
+    let schema = schema.with_static_references();
+    match draft.or_else(|| schema.draft()).unwrap_or_default().ref_overrides_siblings() {
+        true => Cow::Owned(schema.without_ref_siblings()),
+        false => schema,
+    }
+}
+
+// Generated items and the definition they came from (None for anything else)
+type Section<'a> = (Option<&'a str>, Vec<RustItem>);
+
+// Generates the items for every definition (or those reachable from `options.roots`) and the schema itself, and counts
+// how many were merged away
+fn generate<'a>(schema: &'a Schema, options: &Options) -> (Vec<Section<'a>>, Vec<Diagnostic>, usize) {
+    let mut out = Output::default();
+    out.push(RustItem::DeriveCommon);
+    out.push(RustItem::UnitStruct(String::from("Null")));
+    out.overrides = Overrides::new(schema, &options.config, &mut out);
+
+    let (reachable, top_level) = match options.roots.is_empty() {
+        true => (None, !options.openapi),
+        false => {
+            let (reachable, top_level) = reachable(schema, &options.roots, &mut out);
+            (Some(reachable), top_level)
+        },
+    };
+
+    let mut boundaries = vec![(None, 0)];
+    let mut named : HashSet<String> = ["Null", "TopLevel"].iter().map(|name| name.to_string()).collect();
+
+    // Key order in a JSON object carries no meaning, so it shouldn't show up in the output either
+    let mut definitions = schema.definitions.iter()
+        .filter(|(name, _)| match reachable.as_ref() {
+            Some(reachable) => reachable.contains(name.as_str()),
+            None => true,
+        })
+        .filter(|(name, _)| !out.overrides.skip.contains(name.as_str()))
+        .collect::<Vec<_>>();
+    definitions.sort_by_key(|(name, _)| *name);
+
+    for (name, defn) in definitions {
+        boundaries.push((Some(name.as_str()), out.items.len()));
+        named.insert(sanitize(name).to_camel_case());
+        descend(Cow::Borrowed(name.as_str()), defn, &format!("#/definitions/{}", pointer_token(name)), &mut out, true);
+    }
+
+    boundaries.push((None, out.items.len()));
+    if top_level {
+        let start = out.items.len();
+        let top_level = descend(Cow::Borrowed("top_level"), schema, "#", &mut out, true);
+
+        // Schemas that are only a type like null don't declare anything, but there should always be a TopLevel
+        if let Some(top_level) = top_level.filter(|_| !out.items[start..].iter().any(|item| item.name() == Some("TopLevel"))) {
+            out.push(RustItem::TypeAlias(String::from("TopLevel"), top_level));
+        }
+    }
+    out.distinguish_null();
+
+    let generated = out.items.iter().filter_map(RustItem::name).collect::<HashSet<_>>();
+    for key in out.overrides.unused(&generated) {
+        out.diagnose(&key, "Config matches nothing that was generated, ignoring it");
+    }
 
-    for (name, defn) in schema.definitions.iter() {
-        descend(Cow::Borrowed(name.as_str()), &defn, &mut out, true);
+    let renames = out.overrides.renames();
+    for item in out.items.iter_mut() {
+        item.rename(|type_name| renames.get(type_name).cloned());
     }
+    named.extend(out.overrides.names.values().cloned());
 
-    descend(Cow::Borrowed("top_level"), schema, &mut out, true);
+    let mut items = std::mem::take(&mut out.items);
+    let mut sections = Vec::with_capacity(boundaries.len());
+    for (definition, start) in boundaries.into_iter().rev() {
+        sections.push((definition, items.split_off(start)));
+    }
+    sections.reverse();
+
+    let merged = match options.merge {
+        Merge::Never => 0,
+        merge => merge_identical(&mut sections, &named, merge == Merge::IdenticalWithAliases),
+    };
+    for name in box_recursive(sections.iter_mut().flat_map(|(_, items)| items.iter_mut())) {
+        out.diagnose(&name, "Holds itself through type aliases alone, which can't be boxed, so it won't compile");
+    }
+
+    (sections, out.diagnostics, merged)
+}
+
+// Replaces types that are identical apart from their names with one of them (see `Merge`), and returns how many were
+// replaced.  `named` are the types definitions asked for, which keep their names.  Merging types can make the ones
+// using them identical too, so this goes round until nothing else merges.
+fn merge_identical(sections: &mut [Section], named: &HashSet<String>, aliases: bool) -> usize {
+    // A declaration is a type and the attributes in front of it
+    let declarations = sections.iter_mut()
+        .map(|(_, items)| {
+            let mut declarations = Vec::new();
+            let mut declaration = Vec::new();
+            for item in items.drain(..) {
+                let declares = item.name().is_some();
+                declaration.push(item);
+                if declares {
+                    declarations.push(std::mem::take(&mut declaration));
+                }
+            }
+            if !declaration.is_empty() {
+                declarations.push(declaration);
+            }
+            declarations
+        })
+        .collect::<Vec<_>>();
+
+    let mut renamed : HashMap<String, String> = HashMap::new();
+    let resolve = |renamed: &HashMap<String, String>, name: &str| {
+        let mut name = renamed.get(name)?;
+        while let Some(next) = renamed.get(name) {
+            name = next;
+        }
+        Some(name.clone())
+    };
+
+    loop {
+        // Declarations with their own name swapped for Self and the types they use swapped for what they merged into
+        let mut shapes : IndexMap<String, Vec<&str>> = IndexMap::new();
+        for declaration in declarations.iter().flatten() {
+            let name = match declaration.last().and_then(RustItem::name) {
+                Some(name) if !renamed.contains_key(name) => name,
+                _ => continue,
+            };
+
+            let shape = declaration.iter()
+                .cloned()
+                .map(|mut item| {
+                    item.rename(|type_name| match type_name == name {
+                        true => Some(String::from("Self")),
+                        false => resolve(&renamed, type_name),
+                    });
+                    item
+                })
+                .collect::<Vec<_>>();
+            shapes.entry(quote!{ #(#shape)* }.to_string()).or_default().push(name);
+        }
+
+        let mut merges = Vec::new();
+        for names in shapes.into_values().filter(|names| names.len() > 1) {
+            let keep = match names.iter().filter(|name| named.contains(**name)).min() {
+                Some(keep) => *keep,
+                None => *names.iter().min_by_key(|name| (name.len(), **name)).unwrap(),
+            };
+
+            merges.extend(names.iter()
+                .filter(|name| **name != keep && !named.contains(**name))
+                .map(|name| (name.to_string(), keep.to_string())));
+        }
+
+        if merges.is_empty() {
+            break
+        }
+        renamed.extend(merges);
+    }
+
+    for ((_, items), declarations) in sections.iter_mut().zip(declarations) {
+        for mut declaration in declarations {
+            let name = declaration.last().and_then(RustItem::name).map(String::from);
+            match name.and_then(|name| Some((resolve(&renamed, &name)?, name))) {
+                Some((keep, name)) if aliases => items.push(RustItem::TypeAlias(name, keep)),
+                Some(_) => {},
+                None => {
+                    for item in declaration.iter_mut() {
+                        item.rename(|type_name| resolve(&renamed, type_name));
+                    }
+                    items.extend(declaration);
+                },
+            }
+        }
+    }
+
+    info!("Merged {} types into identical ones", renamed.len());
+    renamed.len()
+}
+
+// Types that hold themselves, however indirectly (see RustItem::held_types), have no size, so this boxes whatever
+// closes each such cycle.  Only types in the same strongly connected component can be on a cycle together; slots
+// between them are boxed in the order they were generated, as long as the type held still reaches back.  Returns the
+// types still on a cycle, which can only go through type aliases.
+fn box_recursive<'i, I: Iterator<Item = &'i mut RustItem>>(items: I) -> Vec<String> {
+    let mut items = items.filter(|item| item.name().is_some()).collect::<Vec<_>>();
+    let index = items.iter()
+        .enumerate()
+        .filter_map(|(i, item)| Some((item.name()?.to_string(), i)))
+        .collect::<HashMap<_, _>>();
+    let held = |item: &RustItem| item.held_types().into_iter()
+        .filter_map(|(slot, held)| Some((slot, *index.get(&held)?)))
+        .collect::<Vec<_>>();
+
+    let graph = items.iter().map(|item| held(item).into_iter().map(|(_, j)| j).collect()).collect::<Vec<Vec<_>>>();
+    let components = strongly_connected(&graph);
+
+    for i in 0..items.len() {
+        let mut boxed = HashSet::new();
+        for (slot, j) in held(items[i]) {
+            let slot = match slot {
+                Some(slot) if components[i] == components[j] && !boxed.contains(&slot) => slot,
+                _ => continue,
+            };
+
+            // Whether j still reaches i without going through anything boxed so far
+            let mut seen = HashSet::new();
+            let mut pending = vec![j];
+            let mut reaches = false;
+            while let Some(k) = pending.pop() {
+                if k == i {
+                    reaches = true;
+                    break
+                }
+                if seen.insert(k) {
+                    pending.extend(held(items[k]).into_iter().map(|(_, next)| next).filter(|next| components[*next] == components[i]));
+                }
+            }
+
+            if reaches {
+                items[i].box_slot(slot);
+                boxed.insert(slot);
+            }
+        }
+    }
 
-    quote! {
-        #(#out)*
+    let graph = items.iter().map(|item| held(item).into_iter().map(|(_, j)| j).collect()).collect::<Vec<Vec<_>>>();
+    let components = strongly_connected(&graph);
+    (0..items.len())
+        .filter(|i| graph[*i].iter().any(|j| components[*j] == components[*i]))
+        .filter_map(|i| items[i].name().map(String::from))
+        .collect()
+}
+
+// Tarjan's algorithm, numbering the strongly connected components of `graph` (adjacency lists by node)
+fn strongly_connected(graph: &[Vec<usize>]) -> Vec<usize> {
+    struct State<'g> {
+        graph: &'g [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        next: usize,
+        components: Vec<usize>,
+        count: usize,
+    }
+
+    fn visit(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next);
+        state.low[node] = state.next;
+        state.next += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        for &next in state.graph[node].iter() {
+            match state.index[next] {
+                None => {
+                    visit(state, next);
+                    state.low[node] = state.low[node].min(state.low[next]);
+                },
+                Some(index) if state.on_stack[next] => state.low[node] = state.low[node].min(index),
+                Some(_) => {},
+            }
+        }
+
+        if Some(state.low[node]) == state.index[node] {
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                state.components[member] = state.count;
+                if member == node {
+                    break
+                }
+            }
+            state.count += 1;
+        }
+    }
+
+    let mut state = State {
+        graph,
+        index: vec![None; graph.len()],
+        low: vec![0; graph.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; graph.len()],
+        next: 0,
+        components: vec![0; graph.len()],
+        count: 0,
+    };
+    for node in 0..graph.len() {
+        if state.index[node].is_none() {
+            visit(&mut state, node);
+        }
     }
+
+    state.components
+}
+
+// The definitions reachable through references from `roots`, and whether the schema itself is one of them
+fn reachable<'a>(schema: &'a Schema, roots: &[String], out: &mut Output) -> (HashSet<&'a str>, bool) {
+    let mut reachable = HashSet::new();
+    let mut top_level = false;
+    let mut pending : Vec<&Schema> = Vec::new();
+
+    for root in roots {
+        let name = match root.as_str() {
+            "" | "#" | "#/" => {
+                top_level = true;
+                pending.push(schema);
+                continue
+            },
+            pointer if pointer.starts_with('#') => match definition_of(pointer) {
+                Some(name) => name,
+                None => {
+                    out.diagnose(root, "Roots must be definition names or pointers to definitions, ignoring it");
+                    continue
+                },
+            },
+            name => name.to_string(),
+        };
+
+        match schema.definitions.get_key_value(&name) {
+            Some((name, definition)) => {
+                if reachable.insert(name.as_str()) {
+                    pending.push(definition);
+                }
+            },
+            None => out.diagnose(root, "No such definition, ignoring it"),
+        }
+    }
+
+    while let Some(subschema) = pending.pop() {
+        if subschema.reference.as_deref() == Some("#") && !top_level {
+            top_level = true;
+            pending.push(schema);
+        }
+
+        let name = subschema.reference.as_deref().and_then(definition_of);
+        if let Some((name, definition)) = name.and_then(|name| schema.definitions.get_key_value(&name)) {
+            if reachable.insert(name.as_str()) {
+                pending.push(definition);
+            }
+        }
+
+        pending.extend(subschema.subschemas());
+    }
+
+    (reachable, top_level)
+}
+
+// `token` escaped for use in a JSON pointer
+fn pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+// A pointer into the schema spelled the way descend spells locations: definitions rather than $defs or OpenAPI's
+// components/schemas, and prefixItems and items rather than an index into items and additionalItems
+fn canonical_pointer(pointer: &str) -> String {
+    let pointer = match pointer.strip_prefix("#/components/schemas/") {
+        Some(rest) => Cow::Owned(format!("#/definitions/{}", rest)),
+        None => Cow::Borrowed(pointer),
+    };
+
+    let mut canonical = String::from("#");
+    let mut tokens = pointer.trim_start_matches('#').split('/').filter(|token| !token.is_empty()).peekable();
+    while let Some(token) = tokens.next() {
+        let token = match token {
+            "$defs" => "definitions",
+            "items" if tokens.peek().is_some_and(|index| index.parse::<usize>().is_ok()) => "prefixItems",
+            "additionalItems" => "items",
+            token => token,
+        };
+        canonical.push('/');
+        canonical.push_str(token);
+    }
+    canonical
+}
+
+// The definition name a JSON pointer like `#/definitions/Foo` or `#/$defs/Foo` refers to
+fn definition_of(pointer: &str) -> Option<String> {
+    let name = ["#/definitions/", "#/$defs/", "#/components/schemas/"].iter().find_map(|prefix| pointer.strip_prefix(prefix))?;
+    if name.contains('/') {
+        return None;
+    }
+
+    Some(name.replace("~1", "/").replace("~0", "~"))
 }
@@ -1,9 +1,21 @@
 use proc_macro2::TokenStream as TokenStream;
 use proc_macro2::{Ident, Span};
+use serde_json::Value as JsonValue;
 use quote::{ToTokens, quote, format_ident};
-use zoinks_support::{StringValidatorConfig, NumericValidatorConfig};
+use zoinks_support::{StringValidatorConfig, NumericValidatorConfig, IntegerValidatorConfig, NotValidatorConfig};
 
-#[derive(Debug)]
+#[allow(unused)]
+use log::{info, error, warn, debug};
+
+// Lints the generated code trips over no matter what the schema says, e.g. variants like `N0_5`, enums of very
+// different variants or descriptions that happen to look like markdown lists.  They're allowed on each type rather than
+// for the whole file so the output can be used with `include!`, and clippy::all rather than lint by lint so toolchains
+// that don't know one of them stay quiet.
+fn allowed_lints() -> TokenStream {
+    quote!{ #[allow(non_camel_case_types, clippy::all)] }
+}
+
+#[derive(Debug, Clone)]
 pub(super) enum EnumVariant {
     // {}({}),
     // name, type
@@ -14,30 +26,74 @@ pub(super) enum EnumVariant {
     Unit(String, String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct Enum {
     pub name: String,
     pub variants: Vec<EnumVariant>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct StructField {
     pub name: String,
     pub old_name: String,
     pub field_type: String,
     pub required: bool,
     pub boxed: bool,
+    // For a property of a nullable type, the type it wraps; an optional one is rendered as zoinks_support::Nullable
+    pub nullable: Option<String>,
     pub description: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct Struct {
     pub name: String,
     pub fields: Vec<StructField>,
     pub additional_fields: bool,
+    pub min_properties: Option<u32>,
+    pub max_properties: Option<u32>,
+    // trigger, dependents (JSON property names)
+    pub dependent_required: Vec<(String, Vec<String>)>,
+    // trigger (JSON property name), type the whole object must also deserialize as
+    pub dependent_schemas: Vec<(String, String)>,
+    pub conditional: Option<Conditional>,
+}
+
+// The part of an if schema that can be evaluated at runtime, see zoinks_support::Condition
+#[derive(Debug, Clone)]
+pub(super) struct Condition {
+    // JSON property name, allowed values as JSON text
+    pub properties: Vec<(String, Vec<String>)>,
+    pub required: Vec<String>,
+}
+
+// if/then/else on a struct holding the union of every branch's properties
+#[derive(Debug, Clone)]
+pub(super) struct Conditional {
+    pub condition: Condition,
+    // Types the whole object must also deserialize as when the condition does or doesn't hold
+    pub then_type: Option<String>,
+    pub else_type: Option<String>,
 }
 
-#[derive(Debug)]
+// An enum whose members aren't all strings, matched by value
+#[derive(Debug, Clone)]
+pub(super) struct LiteralEnum {
+    pub name: String,
+    // variant name, value
+    pub variants: Vec<(String, JsonValue)>,
+}
+
+// if/then/else (if/then/else if...) switching on a discriminator property, one variant per branch
+#[derive(Debug, Clone)]
+pub(super) struct ConditionalEnum {
+    pub name: String,
+    // condition, variant name, type
+    pub branches: Vec<(Condition, String, String)>,
+    // variant name, type, or None when the chain ends in an else that nothing is valid against
+    pub otherwise: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
 pub(super) enum RustItem {
     DocComment(String),
 
@@ -65,88 +121,454 @@ pub(super) enum RustItem {
 
     Struct(Struct),
 
+    // pub struct {}(String); with validating Deserialize, Validate and TryFrom impls
+    // name, config
     StringValidator(String, StringValidatorConfig),
+
+    // pub struct {}(f64); with validating Deserialize, Validate and TryFrom impls
+    // name, config
     NumericValidator(String, NumericValidatorConfig),
+
+    // pub struct {}(i64); with validating Deserialize, Validate and TryFrom impls
+    // name, config
+    IntegerValidator(String, IntegerValidatorConfig),
+
+    // pub enum {} { Variant(Type), ... } with a Deserialize impl picking the variant by condition
+    ConditionalEnum(ConditionalEnum),
+
+    // pub enum {} { Variant, ... } with a Deserialize impl comparing against each member's value
+    LiteralEnum(LiteralEnum),
+
+    // pub struct {}({}); rejecting the values a not schema describes
+    // name, base type, config
+    NotValidator(String, String, NotValidatorConfig),
+
+    // pub enum {} {} with a Deserialize impl that always fails, for the schema nothing is valid against
+    Never(String),
 }
 
-impl std::fmt::Display for StructField {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let is_collection = self.field_type.starts_with("Vec");
+impl RustItem {
+    // The type this item declares, None for attributes and doc comments that belong to the next item
+    pub(super) fn name(&self) -> Option<&str> {
+        match self {
+            Self::DocComment(_) | Self::DeriveCommon | Self::DeriveNoSerde | Self::SerdeUntagged => None,
+            Self::TypeAlias(name, _) | Self::TupleStruct(name, _) | Self::UnitStruct(name) | Self::StringValidator(name, _)
+                | Self::NumericValidator(name, _) | Self::IntegerValidator(name, _) | Self::NotValidator(name, _, _) | Self::Never(name) => Some(name),
+            Self::Enum(Enum { name, .. }) | Self::Struct(Struct { name, .. }) | Self::ConditionalEnum(ConditionalEnum { name, .. })
+                | Self::LiteralEnum(LiteralEnum { name, .. }) => Some(name),
+        }
+    }
 
-        let field_type : std::borrow::Cow<str> = match self.boxed {
-            true => format!("Box<{}>", self.field_type).into(),
-            false => (&self.field_type).into(),
-        };
+    // Renames the type this item declares and every type it refers to.  `rename` gets a type name and returns its
+    // new name, if it has one.
+    pub(super) fn rename<F: Fn(&str) -> Option<String>>(&mut self, rename: F) {
+        let rename_in = |type_name: &mut String| *type_name = rename_type(type_name, &rename);
+
+        match self {
+            Self::DeriveCommon | Self::DeriveNoSerde | Self::SerdeUntagged => {},
+            // Only the generator's own comments (`any_of enum: {}`) mention types
+            Self::DocComment(text) => rename_in(text),
+            Self::TypeAlias(name, inner) | Self::TupleStruct(name, inner) | Self::NotValidator(name, inner, _) => {
+                rename_in(name);
+                rename_in(inner);
+            },
+            Self::UnitStruct(name) | Self::StringValidator(name, _) | Self::NumericValidator(name, _) | Self::IntegerValidator(name, _)
+                | Self::LiteralEnum(LiteralEnum { name, .. }) | Self::Never(name) => rename_in(name),
+            Self::Enum(enum_decl) => {
+                rename_in(&mut enum_decl.name);
+
+                // anyOf variants are named after their type, so they follow it unless that would clash or it's now
+                // a path such as `crate::Color`
+                let mut taken = enum_decl.variants.iter()
+                    .map(|variant| match variant {
+                        EnumVariant::Tuple(name, _) | EnumVariant::Unit(name, _) => name.clone(),
+                    })
+                    .collect::<std::collections::HashSet<_>>();
+                for variant in enum_decl.variants.iter_mut() {
+                    if let EnumVariant::Tuple(variant_name, variant_type) = variant {
+                        let named_after_type = variant_name == variant_type;
+                        rename_in(variant_type);
+                        let identifier = variant_type.chars().all(|c| c.is_alphanumeric() || c == '_');
+                        if named_after_type && identifier && !taken.contains(variant_type.as_str()) {
+                            taken.remove(variant_name.as_str());
+                            taken.insert(variant_type.clone());
+                            *variant_name = variant_type.clone();
+                        }
+                    }
+                }
+            },
+            Self::Struct(struct_decl) => {
+                rename_in(&mut struct_decl.name);
+                for field in struct_decl.fields.iter_mut() {
+                    rename_in(&mut field.field_type);
+                    if let Some(nullable) = field.nullable.as_mut() {
+                        rename_in(nullable);
+                    }
+                }
+                for (_, dependent_type) in struct_decl.dependent_schemas.iter_mut() {
+                    rename_in(dependent_type);
+                }
+                if let Some(conditional) = struct_decl.conditional.as_mut() {
+                    conditional.then_type.iter_mut().chain(conditional.else_type.iter_mut()).for_each(rename_in);
+                }
+            },
+            Self::ConditionalEnum(enum_decl) => {
+                rename_in(&mut enum_decl.name);
+                for (_, _, branch_type) in enum_decl.branches.iter_mut() {
+                    rename_in(branch_type);
+                }
+                if let Some((_, otherwise_type)) = enum_decl.otherwise.as_mut() {
+                    rename_in(otherwise_type);
+                }
+            },
+        }
+    }
+
+    // The types this item holds directly rather than behind a Vec, map or Box, each with the slot (field or variant)
+    // holding it if that can be boxed.  A type that holds itself this way, however indirectly, has no size.
+    pub(super) fn held_types(&self) -> Vec<(Option<usize>, String)> {
+        let held = |slot: Option<usize>, rust_type: &str| held_by_value(rust_type).into_iter().map(move |held| (slot, held));
 
-        if self.name != self.old_name {
-            writeln!(f, r#"  #[serde(rename="{}")]"#, self.old_name)?;
+        match self {
+            Self::TypeAlias(_, inner) => held(None, inner).collect(),
+            Self::TupleStruct(_, inner) | Self::NotValidator(_, inner, _) => held(Some(0), inner).collect(),
+            Self::Struct(struct_decl) => struct_decl.fields.iter()
+                .enumerate()
+                .filter(|(_, field)| !field.boxed)
+                .flat_map(|(i, field)| held(Some(i), &field.field_type).chain(field.nullable.iter().flat_map(move |inner| held(Some(i), inner))))
+                .collect(),
+            Self::Enum(enum_decl) => enum_decl.variants.iter()
+                .enumerate()
+                .flat_map(|(i, variant)| match variant {
+                    EnumVariant::Tuple(_, variant_type) => held(Some(i), variant_type).collect(),
+                    EnumVariant::Unit(_, _) => vec![],
+                })
+                .collect(),
+            Self::ConditionalEnum(enum_decl) => enum_decl.branches.iter()
+                .map(|(_, _, branch_type)| branch_type)
+                .chain(enum_decl.otherwise.iter().map(|(_, otherwise_type)| otherwise_type))
+                .enumerate()
+                .flat_map(|(i, branch_type)| held(Some(i), branch_type))
+                .collect(),
+            _ => vec![],
         }
+    }
 
-        if self.required {
-            writeln!(f, "  pub {}: {},", self.name, field_type)
-        } else {
-            if is_collection {
-                writeln!(f, r#"  #[serde(default)]"#)?;
-                writeln!(f, "  pub {}: {},", self.name, field_type)
-            } else {
-                writeln!(f, "  pub {}: Option<{}>,", self.name, field_type)
-            }
+    // Puts what a slot from held_types holds behind a Box
+    pub(super) fn box_slot(&mut self, slot: usize) {
+        match self {
+            Self::TupleStruct(_, inner) | Self::NotValidator(_, inner, _) => *inner = format!("Box<{}>", inner),
+            Self::Struct(struct_decl) => struct_decl.fields[slot].boxed = true,
+            Self::Enum(enum_decl) => if let EnumVariant::Tuple(_, variant_type) = &mut enum_decl.variants[slot] {
+                *variant_type = format!("Box<{}>", variant_type);
+            },
+            Self::ConditionalEnum(enum_decl) => {
+                let branch_type = match (enum_decl.branches.get_mut(slot), enum_decl.otherwise.as_mut()) {
+                    (Some((_, _, branch_type)), _) | (None, Some((_, branch_type))) => branch_type,
+                    (None, None) => return,
+                };
+                *branch_type = format!("Box<{}>", branch_type);
+            },
+            _ => {},
         }
     }
 }
 
-impl std::fmt::Display for Struct {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.additional_fields == false {
-            writeln!(f, r#"#[serde(deny_unknown_fields)]"#)?;
-        }
+// The types a value of `rust_type` contains without any indirection: itself, what's in an Option or a tuple, but not
+// what's in a Vec, map or Box
+fn held_by_value(rust_type: &str) -> Vec<String> {
+    let rust_type = rust_type.trim();
+    let optional = rust_type.strip_prefix("Option<").or_else(|| rust_type.strip_prefix("zoinks_support::Nullable<"));
 
-        writeln!(f, "pub struct {} {{", self.name)?;
-        for field in self.fields.iter() {
-            write!(f, "{}", field)?;
+    if let Some(inner) = optional.and_then(|inner| inner.strip_suffix('>')) {
+        held_by_value(inner)
+    } else if let Some(elements) = rust_type.strip_prefix('(').and_then(|elements| elements.strip_suffix(')')) {
+        // Tuples only hold plain type names, so there are no commas to worry about inside the elements
+        elements.split(',').filter(|element| !element.trim().is_empty()).flat_map(held_by_value).collect()
+    } else if rust_type.contains('<') {
+        vec![]
+    } else {
+        vec![rust_type.to_string()]
+    }
+}
+
+// Renames the types mentioned in a type like `Vec<Foo>`, leaving paths such as `serde_json::Value` alone
+fn rename_type<F: Fn(&str) -> Option<String>>(type_name: &str, rename: F) -> String {
+    let mut renamed = String::with_capacity(type_name.len());
+    let mut rest = type_name;
+
+    while let Some(start) = rest.find(|c: char| c.is_alphanumeric() || c == '_') {
+        let (before, word) = rest.split_at(start);
+        let end = word.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(word.len());
+        let (word, after) = word.split_at(end);
+
+        renamed.push_str(before);
+        match (renamed.ends_with("::"), rename(word)) {
+            (false, Some(new_name)) => renamed.push_str(&new_name),
+            _ => renamed.push_str(word),
         }
-        writeln!(f, "}}")
+        rest = after;
+    }
+    renamed.push_str(rest);
+
+    renamed
+}
+
+impl ToTokens for Condition {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let properties = self.properties.iter()
+            .map(|(name, values)| quote!{ (#name, &[#(#values),*]) });
+        let required = &self.required;
+
+        tokens.extend(quote!{
+            zoinks_support::Condition {
+                properties: &[#(#properties),*],
+                required: &[#(#required),*],
+            }
+        })
     }
 }
 
-impl std::fmt::Display for Enum {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "pub enum {} {{", self.name)?;
-        for variant in self.variants.iter() {
-            write!(f, "{}", variant)?;
+impl StructField {
+    // Whether the property would appear in the serialized object.  An optional array is an `Option` like anything else,
+    // so an empty one still counts.
+    fn present(&self, receiver: &TokenStream) -> TokenStream {
+        let name = Ident::new(&self.name, Span::call_site());
+        match (self.required, self.nullable.is_some()) {
+            (true, _) => quote!{ true },
+            (false, true) => quote!{ !#receiver.#name.is_absent() },
+            (false, false) => quote!{ #receiver.#name.is_some() },
+        }
+    }
+
+    // The opposite of `present`, or None if the property can't be left out
+    fn absent(&self, receiver: &TokenStream) -> Option<TokenStream> {
+        let name = Ident::new(&self.name, Span::call_site());
+        match (self.required, self.nullable.is_some()) {
+            (true, _) => None,
+            (false, true) => Some(quote!{ #receiver.#name.is_absent() }),
+            (false, false) => Some(quote!{ #receiver.#name.is_none() }),
         }
-        writeln!(f, "}}")
     }
 }
 
-impl std::fmt::Display for EnumVariant {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Unit(variant_name, old_name) => {
-                if variant_name != old_name {
-                    writeln!(f, r#"  #[serde(rename="{}")]"#, old_name)?;
+impl Struct {
+    fn field_by_old_name(&self, old_name: &str) -> Option<&StructField> {
+        self.fields.iter().find(|field| field.old_name == old_name)
+    }
+
+    fn has_object_rules(&self) -> bool {
+        self.min_properties.is_some() || self.max_properties.is_some() || !self.dependent_required.is_empty() || !self.dependent_schemas.is_empty() || self.conditional.is_some()
+    }
+
+    // Object-level rules that the field types can't express on their own
+    fn object_rules(&self) -> TokenStream {
+        let mut rules = TokenStream::new();
+
+        if self.min_properties.is_some() || self.max_properties.is_some() {
+            let present = self.fields.iter().map(|field| field.present(&quote!{ self }));
+            rules.extend(quote!{
+                let count = [#(#present),*].iter().filter(|present| **present).count();
+            });
+
+            if let Some(min) = self.min_properties.map(|min| min as usize) {
+                rules.extend(quote!{
+                    if count < #min {
+                        errors.push(path, format!("expected at least {} properties, found {}", #min, count));
+                    }
+                });
+            }
+
+            if let Some(max) = self.max_properties.map(|max| max as usize) {
+                rules.extend(quote!{
+                    if count > #max {
+                        errors.push(path, format!("expected at most {} properties, found {}", #max, count));
+                    }
+                });
+            }
+        }
+
+        for (trigger, dependents) in self.dependent_required.iter() {
+            let trigger_present = match self.field_by_old_name(trigger) {
+                Some(field) => field.present(&quote!{ self }),
+                None => {
+                    warn!("Can't enforce dependencies on {}::{}, it is not a known property", self.name, trigger);
+                    continue
+                },
+            };
+
+            let checks = dependents.iter()
+                .filter_map(|dependent| {
+                    let dependent_absent = match self.field_by_old_name(dependent) {
+                        Some(field) => field.absent(&quote!{ self })?,
+                        None => {
+                            warn!("Can't require {}::{}, it is not a known property", self.name, dependent);
+                            return None
+                        },
+                    };
+                    let message = format!("property `{}` is required when `{}` is present", dependent, trigger);
+                    Some(quote!{
+                        if #trigger_present && #dependent_absent {
+                            errors.push(path, #message);
+                        }
+                    })
+                });
+
+            rules.extend(checks);
+        }
+
+        rules
+    }
+
+    // Wraps the derived (remote = "Self") Deserialize impl so input breaking the object rules is rejected
+    fn deserialize_impl(&self) -> TokenStream {
+        let name = Ident::new(&self.name, Span::call_site());
+
+        // Dependent schemas get checked by deserializing the same input as another type, so buffer it first
+        let (buffer, value) = match self.dependent_schemas.is_empty() && self.conditional.is_none() {
+            true => (quote!{}, quote!{ Self::deserialize(deserializer)? }),
+            false => (
+                quote!{ let json = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?; },
+                quote!{ Self::deserialize(&json).map_err(D::Error::custom)? },
+            ),
+        };
+
+        let dependent_schemas = self.dependent_schemas.iter()
+            .filter_map(|(trigger, dependent_type)| {
+                let trigger_present = match self.field_by_old_name(trigger) {
+                    Some(field) => field.present(&quote!{ value }),
+                    None => {
+                        warn!("Can't apply dependent schema on {}::{}, it is not a known property", self.name, trigger);
+                        return None
+                    },
+                };
+                let dependent_type : syn::Type = syn::parse_str(dependent_type).expect("Unable to parse");
+                let context = format!("when `{}` is present", trigger);
+
+                Some(quote!{
+                    if #trigger_present {
+                        <#dependent_type as serde::Deserialize>::deserialize(&json)
+                            .map_err(|e| D::Error::custom(format!("{} {}", e, #context)))?;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let conditional = match self.conditional.as_ref() {
+            Some(conditional) => {
+                let condition = &conditional.condition;
+                let branch = |branch_type: Option<&String>, context: &str| match branch_type {
+                    Some(branch_type) => {
+                        let branch_type : syn::Type = syn::parse_str(branch_type).expect("Unable to parse");
+                        quote!{
+                            <#branch_type as serde::Deserialize>::deserialize(&json)
+                                .map_err(|e| D::Error::custom(format!("{} {}", e, #context)))?;
+                        }
+                    },
+                    None => quote!{},
+                };
+                let then_branch = branch(conditional.then_type.as_ref(), "(then branch)");
+                let else_branch = branch(conditional.else_type.as_ref(), "(else branch)");
+
+                quote!{
+                    if (#condition).holds(&json) {
+                        #then_branch
+                    } else {
+                        #else_branch
+                    }
                 }
-                writeln!(f, "  {},", variant_name)
             },
-            Self::Tuple(variant_name, type_name) => writeln!(f, "  {}({}),", variant_name, type_name),
+            None => quote!{},
+        };
+
+        quote!{
+            impl<'de> serde::Deserialize<'de> for #name {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>
+                {
+                    use serde::de::Error;
+
+                    #buffer
+                    let value = #value;
+                    #(#dependent_schemas)*
+                    #conditional
+
+                    let mut errors = zoinks_support::ValidationErrors::default();
+                    value.validate_object_rules("", &mut errors);
+                    errors.into_result().map_err(D::Error::custom)?;
+
+                    Ok(value)
+                }
+            }
         }
     }
 }
 
-impl std::fmt::Display for RustItem {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::DocComment(text) => writeln!(f, "/// {}", text),
-            Self::DeriveCommon => write!(f, "#[derive(Debug, serde::Deserialize)]"),
-            Self::DeriveNoSerde => write!(f, "#[derive(Debug)]"),
-            Self::SerdeUntagged => write!(f, "#[serde(untagged)]"),
-            Self::TypeAlias(type_alias, type_source) => write!(f, "pub type {} = {};", type_alias, type_source),
-            Self::Enum(enum_decl) => write!(f, "{}", enum_decl),
-            Self::TupleStruct(struct_name, struct_type) => write!(f, "pub struct {} ({});", struct_name, struct_type),
-            Self::UnitStruct(struct_name) => write!(f, "pub struct {};", struct_name),
-            Self::Struct(struct_decl) => write!(f, "{}", struct_decl),
-            Self::StringValidator(field_name, config) => write!(f, "String {} {:?}", field_name, config),
-            Self::NumericValidator(field_name, config) => write!(f, "Numeric {} {:?}", field_name, config),
+fn validate_impl(name: &Ident, body: TokenStream) -> TokenStream {
+    let (path, errors) = match body.is_empty() {
+        true => (quote!{ _path }, quote!{ _errors }),
+        false => (quote!{ path }, quote!{ errors }),
+    };
+
+    quote!{
+        impl zoinks_support::Validate for #name {
+            fn validate_at(&self, #path: &str, #errors: &mut zoinks_support::ValidationErrors) {
+                #body
+            }
+        }
+    }
+}
+
+// A tuple struct wrapping a single value, plus a Deref impl to get at it
+fn newtype(name: &Ident, inner: &syn::Type) -> TokenStream {
+    quote! {
+        pub struct #name (#inner);
+
+        impl std::ops::Deref for #name {
+            type Target = #inner;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+    }
+}
+
+// A newtype whose Deserialize impl, Validate impl and TryFrom constructor all check the value against `config`
+fn validated_newtype<C: ToTokens>(name: &str, inner: &str, config: &C, checked: TokenStream) -> TokenStream {
+    let name = format_ident!("{}", name);
+    let inner : syn::Type = syn::parse_str(inner).expect("Unable to parse");
+    let newtype = newtype(&name, &inner);
+    let validate = validate_impl(&name, quote!{
+        let config = #config;
+        config.check(#checked, path, errors);
+    });
+
+    quote!{
+        #newtype
+
+        impl<'de> serde::Deserialize<'de> for #name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>
+            {
+                let config = #config;
+
+                deserializer.deserialize_any(config).map(Self)
+            }
+        }
+
+        #validate
+
+        impl std::convert::TryFrom<#inner> for #name {
+            type Error = zoinks_support::ValidationErrors;
+
+            fn try_from(value: #inner) -> Result<Self, Self::Error> {
+                let value = Self(value);
+                zoinks_support::Validate::validate(&value)?;
+                Ok(value)
+            }
         }
     }
 }
@@ -158,12 +580,15 @@ impl ToTokens for RustItem {
                 let doc_comment = quote!{ #[doc=#text] };
                 out.extend(doc_comment);
             },
+            // Every type but an alias starts with its derives, so they bring the allowed lints along
             Self::DeriveCommon => {
-                let derive = quote!{ #[derive(Debug, serde::Deserialize)] };
+                let allow = allowed_lints();
+                let derive = quote!{ #allow #[derive(Debug, serde::Deserialize)] };
                 out.extend(derive);
             },
             Self::DeriveNoSerde => {
-                let derive = quote!{ #[derive(Debug)] };
+                let allow = allowed_lints();
+                let derive = quote!{ #allow #[derive(Debug)] };
                 out.extend(derive);
             },
             Self::SerdeUntagged => {
@@ -174,7 +599,9 @@ impl ToTokens for RustItem {
                 let type_alias = Ident::new(type_alias, Span::call_site());
 
                 let type_source : syn::Type = syn::parse_str(type_source).expect("Unable to parse");
+                let allow = allowed_lints();
                 let the_type = quote!{
+                    #allow
                     pub type #type_alias = #type_source;
                 };
 
@@ -187,7 +614,7 @@ impl ToTokens for RustItem {
                     .map(|variant| {
                         match variant {
                             EnumVariant::Tuple(e_name, e_type) => {
-                                let e_name = Ident::new(&e_name, Span::call_site());
+                                let e_name = Ident::new(e_name, Span::call_site());
                                 let e_type : syn::Type = syn::parse_str(e_type).expect("Unable to parse");
                                 quote!{
                                     #e_name(#e_type)
@@ -198,7 +625,7 @@ impl ToTokens for RustItem {
                                     true => quote!{},
                                     false => quote!{ #[serde(rename=#old_name)]},
                                 };
-                                let name = Ident::new(&name, Span::call_site());
+                                let name = Ident::new(name, Span::call_site());
                                 quote!{
                                     #rename
                                     #name
@@ -208,33 +635,57 @@ impl ToTokens for RustItem {
                     })
                     .collect::<Vec<_>>();
 
+                let validated_variants = enum_decl.variants.iter()
+                    .filter_map(|variant| match variant {
+                        EnumVariant::Tuple(e_name, _) => {
+                            let e_name = Ident::new(e_name, Span::call_site());
+                            Some(quote!{
+                                Self::#e_name(inner) => zoinks_support::Validate::validate_at(inner, path, errors),
+                            })
+                        },
+                        EnumVariant::Unit(..) => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                let validate = match validated_variants.len() {
+                    0 => validate_impl(&name, quote!{}),
+                    n if n == enum_decl.variants.len() => validate_impl(&name, quote!{
+                        match self {
+                            #(#validated_variants)*
+                        }
+                    }),
+                    _ => validate_impl(&name, quote!{
+                        match self {
+                            #(#validated_variants)*
+                            _ => {},
+                        }
+                    }),
+                };
+
                 let the_enum = quote!{
                     pub enum #name {
                         #(#variants),*
                     }
+
+                    #validate
                 };
 
                 out.extend(the_enum);
             },
             Self::TupleStruct(struct_name, struct_type) => {
-                let struct_name = Ident::new(&struct_name, Span::call_site());
+                let struct_name = Ident::new(struct_name, Span::call_site());
 
                 let struct_type : syn::Type = syn::parse_str(struct_type).expect("Unable to parse");
 
-                let deref_impl = quote!{
-                    impl std::ops::Deref for #struct_name {
-                        type Target = #struct_type;
-
-                        fn deref(&self) -> &Self::Target {
-                            &self.0
-                        }
-                    }
-                };
+                let newtype = newtype(&struct_name, &struct_type);
+                let validate = validate_impl(&struct_name, quote!{
+                    zoinks_support::Validate::validate_at(&self.0, path, errors);
+                });
 
                 let tuple_struct = quote! {
-                    pub struct #struct_name (#struct_type);
+                    #newtype
 
-                    #deref_impl
+                    #validate
                 };
 
                 out.extend(tuple_struct);
@@ -242,8 +693,11 @@ impl ToTokens for RustItem {
             }
             Self::UnitStruct(struct_name) => {
                 let struct_name = Ident::new(struct_name, Span::call_site());
+                let validate = validate_impl(&struct_name, quote!{});
                 let unit_struct = quote!{
                     pub struct #struct_name;
+
+                    #validate
                 };
 
                 out.extend(unit_struct);
@@ -254,17 +708,17 @@ impl ToTokens for RustItem {
                     .map(|field| {
                         let name = Ident::new(&field.name, Span::call_site());
 
-                        let is_collection = field.field_type.starts_with("Vec");
-
-                        let field_type : std::borrow::Cow<str> = match field.boxed {
-                            true => format!("Box<{}>", field.field_type).into(),
-                            false => (&field.field_type).into(),
+                        let field_type : std::borrow::Cow<str> = match (field.required, field.nullable.as_ref(), field.boxed) {
+                            (false, Some(inner), true) => format!("zoinks_support::Nullable<Box<{}>>", inner).into(),
+                            (false, Some(inner), false) => format!("zoinks_support::Nullable<{}>", inner).into(),
+                            (_, _, true) => format!("Box<{}>", field.field_type).into(),
+                            (_, _, false) => (&field.field_type).into(),
                         };
                 
                         let field_type = match field.required {
                             true => field_type,
                             false => {
-                                if is_collection {
+                                if field.nullable.is_some() {
                                     field_type
                                 } else {
                                     format!("Option<{}>", field_type).into()
@@ -272,11 +726,13 @@ impl ToTokens for RustItem {
                             }
                         };
 
-                        let field_type : syn::Type = syn::parse_str(&field_type).expect(&format!("Unable to parse: {}", field_type));
+                        let field_type : syn::Type = syn::parse_str(&field_type).unwrap_or_else(|_| panic!("Unable to parse: {}", field_type));
 
-                        let serde_default = match is_collection && !field.required {
-                            true => quote!{ #[serde(default)] },
-                            false => quote!{}
+                        // A required property that's an Option would otherwise be None when it's missing
+                        let serde_default = match (field.required, field.nullable.is_some()) {
+                            (false, true) => quote!{ #[serde(default)] },
+                            (true, true) => quote!{ #[serde(deserialize_with = "zoinks_support::required")] },
+                            (_, false) => quote!{}
                         };
 
                         let rename = match field.name == field.old_name {
@@ -306,48 +762,299 @@ impl ToTokens for RustItem {
                     true => quote! {},
                 };
 
+                let field_checks = struct_decl.fields.iter()
+                    .map(|field| {
+                        let name = Ident::new(&field.name, Span::call_site());
+                        let old_name = &field.old_name;
+                        quote!{
+                            zoinks_support::Validate::validate_at(&self.#name, &zoinks_support::field_path(path, #old_name), errors);
+                        }
+                    });
+
+                // Structs with object rules derive Deserialize as an inherent fn and wrap it with a checking impl
+                let (remote, object_rules, deserialize) = match struct_decl.has_object_rules() {
+                    true => {
+                        let object_rules = struct_decl.object_rules();
+                        let (path, errors) = match object_rules.is_empty() {
+                            true => (quote!{ _path }, quote!{ _errors }),
+                            false => (quote!{ path }, quote!{ errors }),
+                        };
+                        (
+                            quote!{ #[serde(remote = "Self")] },
+                            quote!{
+                                impl #struct_name {
+                                    fn validate_object_rules(&self, #path: &str, #errors: &mut zoinks_support::ValidationErrors) {
+                                        #object_rules
+                                    }
+                                }
+                            },
+                            struct_decl.deserialize_impl(),
+                        )
+                    },
+                    false => (quote!{}, quote!{}, quote!{}),
+                };
+
+                let validate_object_rules = match struct_decl.has_object_rules() {
+                    true => quote!{ self.validate_object_rules(path, errors); },
+                    false => quote!{},
+                };
+
+                let validate = validate_impl(&struct_name, quote!{
+                    #(#field_checks)*
+                    #validate_object_rules
+                });
+
                 let the_struct = quote!{
+                    #remote
                     #deny
                     pub struct #struct_name {
                         #(#fields),*
                     }
+
+                    #object_rules
+
+                    #deserialize
+
+                    #validate
                 };
 
                 out.extend(the_struct);
             },
             Self::NumericValidator(name, config) => {
-                let name = format_ident!("{}", name);
-                let validator = quote!{
+                out.extend(validated_newtype(name, "f64", config, quote!{ self.0 }));
+            },
+            Self::IntegerValidator(name, config) => {
+                out.extend(validated_newtype(name, "i64", config, quote!{ self.0 }));
+            },
+            Self::StringValidator(name, config) => {
+                out.extend(validated_newtype(name, "String", config, quote!{ &self.0 }));
+            },
+            Self::LiteralEnum(enum_decl) => {
+                let name = Ident::new(&enum_decl.name, Span::call_site());
+
+                let variants = enum_decl.variants.iter()
+                    .map(|(v_name, _)| Ident::new(v_name, Span::call_site()))
+                    .collect::<Vec<_>>();
+
+                let matchers = enum_decl.variants.iter()
+                    .map(|(_, value)| match value {
+                        JsonValue::String(s) => quote!{ json.as_str() == Some(#s) },
+                        JsonValue::Bool(b) => quote!{ json.as_bool() == Some(#b) },
+                        JsonValue::Null => quote!{ json.is_null() },
+                        JsonValue::Number(n) => {
+                            let n = n.as_f64().expect("Numbers are representable as f64");
+                            quote!{ json.as_f64() == Some(#n) }
+                        },
+                        composite => {
+                            let literal = composite.to_string();
+                            quote!{ zoinks_support::matches_literal(&json, #literal) }
+                        },
+                    });
+
+                let serializers = enum_decl.variants.iter()
+                    .map(|(_, value)| match value {
+                        JsonValue::String(s) => quote!{ serializer.serialize_str(#s) },
+                        JsonValue::Bool(b) => quote!{ serializer.serialize_bool(#b) },
+                        JsonValue::Null => quote!{ serializer.serialize_unit() },
+                        JsonValue::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+                            (Some(n), _, _) => quote!{ serializer.serialize_i64(#n) },
+                            (None, Some(n), _) => quote!{ serializer.serialize_u64(#n) },
+                            (None, None, n) => {
+                                let n = n.expect("Numbers are representable as f64");
+                                quote!{ serializer.serialize_f64(#n) }
+                            },
+                        },
+                        composite => {
+                            let literal = composite.to_string();
+                            quote!{
+                                match serde_json::from_str::<serde_json::Value>(#literal) {
+                                    Ok(value) => serde::Serialize::serialize(&value, serializer),
+                                    Err(e) => Err(serde::ser::Error::custom(e)),
+                                }
+                            }
+                        },
+                    });
+
+                let expected = format!("one of {}", enum_decl.variants.iter().map(|(_, value)| value.to_string()).collect::<Vec<_>>().join(", "));
+                let validate = validate_impl(&name, quote!{});
+
+                out.extend(quote!{
+                    pub enum #name {
+                        #(#variants),*
+                    }
+
                     impl<'de> serde::Deserialize<'de> for #name {
                         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                         where
                             D: serde::Deserializer<'de>
                         {
-                            let config = #config;
+                            use serde::de::Error;
+
+                            let json = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+                            #(
+                                if #matchers {
+                                    return Ok(Self::#variants)
+                                }
+                            )*
 
-                            deserializer.deserialize_any(config).map(|n| Self(n))
+                            Err(D::Error::custom(format!("invalid value: {}, expected {}", json, #expected)))
                         }
                     }
-                };
 
-                out.extend(validator);
+                    impl serde::Serialize for #name {
+                        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                        where
+                            S: serde::Serializer
+                        {
+                            match self {
+                                #(Self::#variants => #serializers,)*
+                            }
+                        }
+                    }
+
+                    #validate
+                });
             },
-            Self::StringValidator(name, config) => {
-                let name = format_ident!("{}", name);
-                let validator = quote!{
+            Self::NotValidator(name, base_type, config) => {
+                let name = Ident::new(name, Span::call_site());
+                let inner : syn::Type = syn::parse_str(base_type).expect("Unable to parse");
+                let newtype = newtype(&name, &inner);
+
+                // Rust values can only be checked if we can turn them back into JSON
+                let as_json = match base_type.as_str() {
+                    "serde_json::Value" => Some(quote!{ &self.0 }),
+                    "String" => Some(quote!{ &serde_json::Value::from(self.0.as_str()) }),
+                    "f64" | "bool" => Some(quote!{ &serde_json::Value::from(self.0) }),
+                    _ => None,
+                };
+                let check = match as_json {
+                    Some(as_json) => quote!{
+                        let config = #config;
+                        config.check(#as_json, path, errors);
+                    },
+                    None => quote!{},
+                };
+                let validate = validate_impl(&name, quote!{
+                    zoinks_support::Validate::validate_at(&self.0, path, errors);
+                    #check
+                });
+
+                out.extend(quote!{
+                    #newtype
+
                     impl<'de> serde::Deserialize<'de> for #name {
                         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
                         where
                             D: serde::Deserializer<'de>
                         {
+                            use serde::de::Error;
+
                             let config = #config;
+                            let json = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+                            if !config.valid(&json) {
+                                return Err(D::Error::custom(format!("invalid value: {}, expected {}", json, config)))
+                            }
 
-                            deserializer.deserialize_any(config).map(|n| Self(n))
+                            <#inner as serde::Deserialize>::deserialize(json).map(Self).map_err(D::Error::custom)
                         }
                     }
+
+                    #validate
+
+                    impl std::convert::TryFrom<#inner> for #name {
+                        type Error = zoinks_support::ValidationErrors;
+
+                        fn try_from(value: #inner) -> Result<Self, Self::Error> {
+                            let value = Self(value);
+                            zoinks_support::Validate::validate(&value)?;
+                            Ok(value)
+                        }
+                    }
+                });
+            },
+            Self::Never(name) => {
+                let name = Ident::new(name, Span::call_site());
+
+                out.extend(quote!{
+                    pub enum #name {}
+
+                    impl<'de> serde::Deserialize<'de> for #name {
+                        fn deserialize<D>(_deserializer: D) -> Result<Self, D::Error>
+                        where
+                            D: serde::Deserializer<'de>
+                        {
+                            Err(<D::Error as serde::de::Error>::custom("no value is valid here"))
+                        }
+                    }
+
+                    impl zoinks_support::Validate for #name {
+                        fn validate_at(&self, _path: &str, _errors: &mut zoinks_support::ValidationErrors) {
+                            match *self {}
+                        }
+                    }
+                });
+            },
+            Self::ConditionalEnum(enum_decl) => {
+                let name = Ident::new(&enum_decl.name, Span::call_site());
+
+                let variants = enum_decl.branches.iter()
+                    .map(|(_, v_name, v_type)| (v_name, v_type))
+                    .chain(enum_decl.otherwise.iter().map(|(v_name, v_type)| (v_name, v_type)))
+                    .map(|(v_name, v_type)| {
+                        let v_name = Ident::new(v_name, Span::call_site());
+                        let v_type : syn::Type = syn::parse_str(v_type).expect("Unable to parse");
+                        (v_name, v_type)
+                    })
+                    .collect::<Vec<_>>();
+
+                // Without an otherwise variant a value that matches no branch isn't valid
+                let otherwise = match enum_decl.otherwise.as_ref() {
+                    Some(_) => {
+                        let (otherwise_name, otherwise_type) = variants.last().unwrap();
+                        quote!{ <#otherwise_type as serde::Deserialize>::deserialize(&json).map(Self::#otherwise_name).map_err(D::Error::custom) }
+                    },
+                    None => quote!{ Err(D::Error::custom(format!("invalid value: {}, expected it to match one of the if schemas", json))) },
                 };
 
-                out.extend(validator);
+                let declarations = variants.iter().map(|(v_name, v_type)| quote!{ #v_name(#v_type) });
+                let checks = variants.iter().map(|(v_name, _)| quote!{
+                    Self::#v_name(inner) => zoinks_support::Validate::validate_at(inner, path, errors),
+                });
+                let validate = validate_impl(&name, quote!{
+                    match self {
+                        #(#checks)*
+                    }
+                });
+
+                let branches = enum_decl.branches.iter()
+                    .zip(variants.iter())
+                    .map(|((condition, _, _), (v_name, v_type))| quote!{
+                        if (#condition).holds(&json) {
+                            return <#v_type as serde::Deserialize>::deserialize(&json).map(Self::#v_name).map_err(D::Error::custom)
+                        }
+                    });
+
+                out.extend(quote!{
+                    pub enum #name {
+                        #(#declarations),*
+                    }
+
+                    impl<'de> serde::Deserialize<'de> for #name {
+                        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                        where
+                            D: serde::Deserializer<'de>
+                        {
+                            use serde::de::Error;
+
+                            let json = <serde_json::Value as serde::Deserialize>::deserialize(deserializer)?;
+                            #(#branches)*
+
+                            #otherwise
+                        }
+                    }
+
+                    #validate
+                });
             },
         }
     }
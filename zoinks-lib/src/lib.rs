@@ -1,33 +1,150 @@
 // Parses a JSON schema definition to Rust objects
 mod parser;
-pub use parser::Schema;
+pub use parser::{Draft, Schema};
+
+// Reads schemas written in JSON, or YAML and JSON5 with the features of the same name
+mod format;
+pub use format::{Format, ParseError};
+
+// Reads the schemas in an OpenAPI document
+mod openapi;
+pub use openapi::from_openapi;
+
+// What to call things and what to leave out
+mod config;
+pub use config::Config;
 
 // Generates rust objects to parse an implementation of a specific schema
 mod generator;
-pub use generator::genimpl;
+pub use generator::{Diagnostic, Grouping, Merge, Modules, Options, genimpl, genimpl_with_diagnostics, genimpl_with_options, genmodules};
+
+// Validates JSON documents against a schema at runtime
+mod validator;
+pub use validator::{ValidationError, validate};
+
+// Lists what in a schema the generated types don't enforce
+mod report;
+pub use report::{unsupported_keywords, unsupported_keywords_with_options};
+
+use std::path::Path;
 
 #[allow(unused)]
 use log::{error, warn, info, debug, trace};
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::quote;
 
 // Reads a JSON schema definition in and prints parser objects to stdout
 pub fn schemafile2print(input_fn: &str) -> String {
+    schemafile2print_with_options(input_fn, &Options::default()).unwrap_or_else(|e| panic!("Unable to read {}: {}", input_fn, e))
+}
+
+// Like `schemafile2print`, with control over what gets generated, and returning what went wrong rather than panicking
+pub fn schemafile2print_with_options(input_fn: &str, options: &Options) -> std::io::Result<String> {
     info!("Reading file");
-    let schema_string = std::fs::read_to_string(input_fn).unwrap();
+    let schema_string = std::fs::read_to_string(input_fn)?;
+
+    let schema = read_schema(input_fn, &schema_string, options)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    report::check_strict(&schema, options).map_err(strict_error)?;
 
-    schema2print(&schema_string)
+    Ok(print(&schema, Some(input_fn), options))
+}
+
+// Reads the contents of `input_fn` the way `options` say to: in the format its extension suggests unless
+// `options.format` says otherwise, and as an OpenAPI document if `options.openapi` is set
+pub fn read_schema<'a>(input_fn: &str, input: &'a str, options: &Options) -> Result<Schema<'a>, ParseError> {
+    let format = options.format.unwrap_or_else(|| Format::from_path(input_fn));
+    match options.openapi {
+        true => {
+            info!("Parsing {} OpenAPI document", format);
+            from_openapi(input, format)
+        },
+        false => {
+            info!("Parsing {}", format);
+            format.parse(input)
+        },
+    }
 }
 
 // Reads a JSON schema definition in and prints parser objects to stdout
 pub fn schema2print(input_str: &str) -> String {
     info!("Parsing JSON");
-    let schema = serde_json::from_str::<Schema>(&input_str).unwrap();
+    let schema = serde_json::from_str::<Schema>(input_str).unwrap();
+
+    print(&schema, None, &Options::default())
+}
 
+fn print(schema: &Schema, source: Option<&str>, options: &Options) -> String {
     info!("Generating structs");
-    let imp = genimpl(&schema);
+    let (imp, _) = genimpl_with_options(schema, options);
+
+    info!("Formatting");
+    let file = format_file(imp, source);
 
     info!("Done");
 
-    format!("{:#}", imp)
+    file
+}
+
+// Reads a JSON schema definition in and writes parser objects to a directory, a file per group of definitions (see
+// `Options::grouping`) and a mod.rs that re-exports all of them
+pub fn schemafile2dir<P: AsRef<Path>>(input_fn: &str, out_dir: P, options: &Options) -> std::io::Result<()> {
+    info!("Reading file");
+    let schema_string = std::fs::read_to_string(input_fn)?;
+
+    let schema = read_schema(input_fn, &schema_string, options)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    report::check_strict(&schema, options).map_err(strict_error)?;
+
+    info!("Generating structs");
+    let generated = genmodules(&schema, options);
+
+    info!("Writing files");
+    let out_dir = out_dir.as_ref();
+    std::fs::create_dir_all(out_dir)?;
+
+    let mut modules = Vec::with_capacity(generated.modules.len());
+    for (name, items) in generated.modules.iter() {
+        let file = format_file(quote!{ use super::*; #items }, Some(input_fn));
+        std::fs::write(out_dir.join(format!("{}.rs", name)), file)?;
+        modules.push(Ident::new(name, Span::call_site()));
+    }
+
+    let root = &generated.root;
+    // Not every module needs something from the others
+    let mod_rs = quote!{
+        #(
+            mod #modules;
+            #[allow(unused_imports)]
+            pub use self::#modules::*;
+        )*
+
+        #root
+    };
+    let file = format_file(mod_rs, Some(input_fn));
+    std::fs::write(out_dir.join("mod.rs"), file)?;
+
+    info!("Done");
+
+    Ok(())
+}
+
+fn strict_error(unsupported: Vec<Diagnostic>) -> std::io::Error {
+    let message = format!("{} keywords won't be enforced, starting with {}", unsupported.len(), unsupported[0]);
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+// Pretty prints generated code under a header saying where it came from
+fn format_file(tokens: TokenStream2, source: Option<&str>) -> String {
+    let file = syn::parse2::<syn::File>(tokens).expect("Generated code should parse");
+    let body = prettyplease::unparse(&file);
+
+    let header = match source {
+        Some(source) => format!("// Generated by zoinks {} from {}, do not edit.", env!("CARGO_PKG_VERSION"), source),
+        None => format!("// Generated by zoinks {}, do not edit.", env!("CARGO_PKG_VERSION")),
+    };
+
+    format!("{}\n\n{}", header, body)
 }
 
 #[cfg(test)]
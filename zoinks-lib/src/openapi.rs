@@ -0,0 +1,148 @@
+use indexmap::IndexMap;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::format::{Format, ParseError};
+use crate::parser::Schema;
+
+const COMPONENTS: &str = "#/components/schemas/";
+
+// OpenAPI's own annotations, which mean nothing to the generated types
+const ANNOTATIONS: &[&str] = &["example", "xml", "externalDocs"];
+
+// The part of an OpenAPI document that describes data, the paths and operations are of no interest here
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "'de: 'a"))]
+struct Document<'a> {
+    openapi: String,
+    #[serde(default)]
+    components: Components<'a>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(bound(deserialize = "'de: 'a"))]
+struct Components<'a> {
+    #[serde(default)]
+    schemas: IndexMap<String, Schema<'a>>,
+}
+
+/// Reads an OpenAPI 3.0 or 3.1 document into a schema whose definitions are its `components/schemas`, and whose
+/// references to `#/components/schemas/Pet` point at `#/definitions/Pet` instead.  OpenAPI's additions to JSON Schema
+/// become what JSON Schema would say:
+///
+/// - `nullable: true` (3.0) allows null too
+/// - `discriminator` on a `oneOf` or `anyOf` of references picks the alternative by the property it names, with
+///   if/then/else, so the generated type is an enum with a variant per alternative
+/// - `readOnly` and `writeOnly` properties aren't required, since they're left out of requests and responses
+///   respectively
+///
+/// As in OpenAPI 3.0 anything next to a `$ref` is ignored there, while 3.1 documents are read as 2020-12.
+pub fn from_openapi(input: &str, format: Format) -> Result<Schema<'_>, ParseError> {
+    let document = format.read::<Document>(input)?;
+    if !document.openapi.starts_with("3.") {
+        return Err(ParseError::new(format!("Only OpenAPI 3.0 and 3.1 documents are supported, this is {}", document.openapi), None))
+    }
+
+    let mut schema = Schema::with_definitions(document.components.schemas);
+
+    let mut pending = vec![&mut schema];
+    while let Some(schema) = pending.pop() {
+        from_dialect(schema);
+        pending.extend(schema.subschemas_mut());
+    }
+
+    match document.openapi.starts_with("3.0") {
+        true => Ok(schema.without_ref_siblings()),
+        false => Ok(schema),
+    }
+}
+
+// Rewrites a single schema, not the ones inside it
+fn from_dialect(schema: &mut Schema) {
+    if let Some(definition) = schema.reference.as_deref().and_then(|reference| reference.strip_prefix(COMPONENTS)) {
+        schema.reference = Some(format!("#/definitions/{}", definition).into());
+    }
+
+    for annotation in ANNOTATIONS {
+        schema.unknown.shift_remove(*annotation);
+    }
+
+    // Read only properties aren't in requests, write only ones aren't in responses
+    let one_way = |property: &Schema| ["readOnly", "writeOnly"].iter().any(|keyword| property.unknown.get(*keyword) == Some(&JsonValue::Bool(true)));
+    let properties = &schema.properties;
+    schema.required.retain(|required| !properties.get(required.as_str()).is_some_and(one_way));
+
+    if let Some(JsonValue::Object(discriminator)) = schema.unknown.get("discriminator").cloned() {
+        if let Some(branches) = discriminated(schema, &discriminator) {
+            schema.unknown.shift_remove("discriminator");
+            schema.one_of.clear();
+            schema.any_of.clear();
+
+            // Ending in false keeps other values of the property invalid, as they are in OpenAPI
+            let chain = branches.into_iter().rev().fold(Schema::nothing(), |otherwise, (property, value, reference)| {
+                let mut condition = Schema::default();
+                condition.properties.insert(property.clone().into(), Schema::constant_of(value.into()));
+                condition.required.push(property);
+
+                let mut link = Schema::default();
+                link.if_schema = Some(Box::new(condition));
+                link.then_schema = Some(Box::new(Schema::reference_to(reference)));
+                link.else_schema = Some(Box::new(otherwise));
+                link
+            });
+            schema.if_schema = chain.if_schema;
+            schema.then_schema = chain.then_schema;
+            schema.else_schema = chain.else_schema;
+        }
+    }
+
+    // In 3.1 null is a type like any other
+    if schema.unknown.shift_remove("nullable") == Some(JsonValue::Bool(true)) {
+        if !schema.instance_type.is_empty() {
+            if !schema.instance_type.iter().any(|instance_type| instance_type == "null") {
+                schema.instance_type.push(String::from("null"));
+            }
+        } else if *schema != Schema::default() {
+            let inner = std::mem::take(schema);
+            let description = inner.description.clone();
+            *schema = Schema::any_of(vec![inner, Schema::of_type("null")]);
+            schema.description = description;
+        }
+    }
+}
+
+// The property, value and reference for each alternative a discriminator picks between, or None if they aren't all
+// references.  Alternatives the mapping doesn't mention go by the name of the schema they refer to.
+fn discriminated(schema: &Schema, discriminator: &serde_json::Map<String, JsonValue>) -> Option<Vec<(String, String, String)>> {
+    let property = discriminator.get("propertyName")?.as_str()?;
+    let alternatives = match schema.one_of.is_empty() {
+        true => &schema.any_of,
+        false => &schema.one_of,
+    };
+    if alternatives.is_empty() {
+        return None
+    }
+
+    let reference = |target: &str| match target.strip_prefix(COMPONENTS) {
+        Some(definition) => format!("#/definitions/{}", definition),
+        None if target.contains('/') => target.to_string(),
+        None => format!("#/definitions/{}", target),
+    };
+
+    let mut branches = Vec::new();
+    if let Some(JsonValue::Object(mapping)) = discriminator.get("mapping") {
+        for (value, target) in mapping.iter() {
+            branches.push((property.to_string(), value.clone(), reference(target.as_str()?)));
+        }
+    }
+
+    for alternative in alternatives.iter() {
+        let target = alternative.reference.as_deref().map(reference)?;
+        if !branches.iter().any(|(_, _, mapped)| *mapped == target) {
+            let name = target.rsplit('/').next().unwrap_or_default().replace("~1", "/").replace("~0", "~");
+            branches.push((property.to_string(), name, target));
+        }
+    }
+
+    Some(branches)
+}
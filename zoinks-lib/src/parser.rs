@@ -1,10 +1,67 @@
 use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
 
 use serde_json::Value as JsonValue;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use serde::de::{MapAccess, Visitor, value::MapAccessDeserializer};
 use indexmap::IndexMap;
 
-#[derive(Debug, Deserialize)]
+// Keywords that only describe a value, so there's nothing to enforce
+pub(crate) const ANNOTATIONS: &[&str] = &["$comment", "examples", "readOnly", "writeOnly", "deprecated", "contentMediaType", "contentEncoding", "contentSchema"];
+
+/// A JSON Schema dialect, usually given by `$schema`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Draft {
+    Draft4,
+    Draft6,
+    Draft7,
+    Draft201909,
+    #[default]
+    Draft202012,
+}
+
+impl Draft {
+    /// The draft a `$schema` URI such as `http://json-schema.org/draft-07/schema#` stands for
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        let uri = uri.trim_end_matches('#').trim_end_matches('/');
+        let path = uri.strip_prefix("https://").or_else(|| uri.strip_prefix("http://")).unwrap_or(uri);
+
+        match path.strip_prefix("json-schema.org/")? {
+            "draft-04/schema" => Some(Self::Draft4),
+            "draft-06/schema" => Some(Self::Draft6),
+            "draft-07/schema" => Some(Self::Draft7),
+            "draft/2019-09/schema" => Some(Self::Draft201909),
+            "draft/2020-12/schema" => Some(Self::Draft202012),
+            _ => None,
+        }
+    }
+
+    /// Before 2019-09 `$ref` replaced the schema it was in, so anything next to it was ignored
+    pub fn ref_overrides_siblings(&self) -> bool {
+        *self <= Self::Draft7
+    }
+}
+
+impl FromStr for Draft {
+    type Err = String;
+
+    /// Accepts `4`, `draft-04`, `2020-12` and the like
+    fn from_str(draft: &str) -> Result<Self, Self::Err> {
+        match draft.trim_start_matches("draft").trim_start_matches('-') {
+            "4" | "04" => Ok(Self::Draft4),
+            "6" | "06" => Ok(Self::Draft6),
+            "7" | "07" => Ok(Self::Draft7),
+            "2019-09" => Ok(Self::Draft201909),
+            "2020-12" => Ok(Self::Draft202012),
+            _ => Err(format!("Unknown draft {}, expected one of 4, 6, 7, 2019-09 or 2020-12", draft)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 #[serde(untagged)]
 pub enum AdditionalProperties<'a> {
     Boolean(bool),
@@ -12,12 +69,43 @@ pub enum AdditionalProperties<'a> {
     Schema(Box<Schema<'a>>),
 }
 
-#[derive(Debug, Deserialize)]
+// Draft 4 made exclusiveMinimum and exclusiveMaximum flags saying whether minimum and maximum are exclusive
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Bound {
+    Number(f64),
+    Flag(bool),
+}
+
+// Until 2020-12 introduced prefixItems, an array of schemas in items described a tuple
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Items<'a> {
+    #[serde(borrow)]
+    Schema(Box<Schema<'a>>),
+    Tuple(Vec<Schema<'a>>),
+}
+
+// Until 2019-09 split it into dependentRequired and dependentSchemas, dependencies held both
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+enum Dependency<'a> {
+    Required(Vec<String>),
+    #[serde(borrow)]
+    Schema(Box<Schema<'a>>),
+}
+
+/// A schema as 2020-12 would put it.  Keywords from earlier drafts that 2020-12 replaced (`dependencies`, boolean
+/// `exclusiveMaximum`, arrays of `items` and `additionalItems`) are read into their replacements, whichever draft the
+/// schema claims to be, since no draft gives them a different meaning.  The one thing that does depend on the draft,
+/// what happens to keywords next to `$ref`, is up to whoever uses the schema (see `Draft::ref_overrides_siblings`).
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
 #[serde(rename_all="camelCase")]
-#[serde(deny_unknown_fields)]
+#[serde(remote = "Self")]
 pub struct Schema<'a> {
     // https://json-schema.org/draft/2020-12/json-schema-core.html#rfc.section.8.2.1 §8.2.1
-    #[serde(rename="$id")]
+    // Draft 4 called it id
+    #[serde(rename="$id", alias="id")]
     pub id: Option<Cow<'a, str>>,
 
     #[serde(rename="$ref")]
@@ -26,6 +114,27 @@ pub struct Schema<'a> {
     #[serde(rename="$schema")]
     pub schema_uri: Option<Cow<'a, str>>,
 
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §8.2.2
+    /// Names this schema, so `$ref: "#name"` can refer to it wherever it is
+    #[serde(rename="$anchor")]
+    pub anchor: Option<Cow<'a, str>>,
+
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §8.2.3.2
+    #[serde(rename="$dynamicAnchor")]
+    pub dynamic_anchor: Option<Cow<'a, str>>,
+
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §8.2.3.2
+    #[serde(rename="$dynamicRef")]
+    pub dynamic_reference: Option<Cow<'a, str>>,
+
+    // https://json-schema.org/draft/2019-09/json-schema-core.html §8.2.4.2, $dynamicAnchor in 2020-12
+    #[serde(rename="$recursiveAnchor")]
+    pub recursive_anchor: Option<bool>,
+
+    // https://json-schema.org/draft/2019-09/json-schema-core.html §8.2.4.2, $dynamicRef in 2020-12
+    #[serde(rename="$recursiveRef")]
+    pub recursive_reference: Option<Cow<'a, str>>,
+
     // https://github.com/serde-rs/serde/issues/1413
     // https://json-schema.org/draft/2020-12/json-schema-validation.html §9.1
     pub description: Option<Cow<'a, str>>,
@@ -52,9 +161,22 @@ pub struct Schema<'a> {
     #[serde(default)]
     pub one_of: Vec<Schema<'a>>,
 
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §10.3.1.1
     #[serde(default)]
+    pub prefix_items: Vec<Schema<'a>>,
+
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §10.3.1.2
+    /// Applies to the items after `prefix_items`
+    #[serde(skip)]
     pub items: Option<Box<Schema<'a>>>,
 
+    #[serde(rename="items", default)]
+    items_keyword: Option<Items<'a>>,
+
+    // https://json-schema.org/draft/2019-09/json-schema-core.html §9.3.1.2, items in 2020-12
+    #[serde(default)]
+    additional_items: Option<AdditionalProperties<'a>>,
+
     #[serde(default)]
     #[serde(rename="enum")]
     pub enums: Vec<JsonValue>,
@@ -80,8 +202,12 @@ pub struct Schema<'a> {
     pub maximum: Option<f64>,
 
     // https://json-schema.org/draft/2020-12/json-schema-validation.html §6.2.3
+    #[serde(skip)]
     pub exclusive_maximum: Option<f64>,
 
+    #[serde(rename="exclusiveMaximum", default)]
+    exclusive_maximum_keyword: Option<Bound>,
+
     // https://json-schema.org/draft/2020-12/json-schema-validation.html §6.2.4
     /// The value of "minimum" MUST be a number, representing an inclusive lower limit for a numeric instance.
     /// If the instance is a number, then this keyword validates only if the instance is greater than or exactly equal to "minimum".
@@ -90,8 +216,12 @@ pub struct Schema<'a> {
     // https://json-schema.org/draft/2020-12/json-schema-validation.html §6.2.5
     /// The value of "exclusiveMinimum" MUST be a number, representing an exclusive lower limit for a numeric instance.
     /// If the instance is a number, then the instance is valid only if it has a value strictly greater than (not equal to) "exclusiveMinimum".
+    #[serde(skip)]
     pub exclusive_minimum: Option<f64>,
 
+    #[serde(rename="exclusiveMinimum", default)]
+    exclusive_minimum_keyword: Option<Bound>,
+
     // 6.3. Validation Keywords for Strings
     // https://json-schema.org/draft/2020-12/json-schema-validation.html §6.3.1
     /// The value of this keyword MUST be a non-negative integer.
@@ -136,16 +266,390 @@ pub struct Schema<'a> {
     #[serde(default)]
     pub dependent_required: IndexMap<String, Vec<String>>,
 
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §10.2.2.4
+    #[serde(default)]
+    pub dependent_schemas: IndexMap<String, Schema<'a>>,
+
+    // https://json-schema.org/draft-07/json-schema-validation.html §6.5.7
+    #[serde(default)]
+    dependencies: IndexMap<String, Dependency<'a>>,
+
     // https://json-schema.org/draft/2020-12/json-schema-validation.html §7
     pub format: Option<Cow<'a, str>>,
 
     // https://json-schema.org/draft/2020-12/json-schema-validation.html §9.2
     pub default: Option<JsonValue>,
 
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §10.2.2.1
+    #[serde(rename="if")]
+    pub if_schema: Option<Box<Schema<'a>>>,
+
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §10.2.2.2
+    #[serde(rename="then")]
+    pub then_schema: Option<Box<Schema<'a>>>,
+
+    // https://json-schema.org/draft/2020-12/json-schema-core.html §10.2.2.3
+    #[serde(rename="else")]
+    pub else_schema: Option<Box<Schema<'a>>>,
+
     // https://json-schema.org/draft/2020-12/json-schema-core.html §10.2.1.4
     pub not: Option<Box<Schema<'a>>>,
 
     // https://json-schema.org/draft/2020-12/json-schema-core.html §10.3.2.2
     #[serde(default)]
     pub pattern_properties: IndexMap<String, Schema<'a>>,
+
+    /// Everything else, such as `examples`, `$comment` or `x-` extensions, see `unsupported_keywords`
+    #[serde(flatten)]
+    pub unknown: IndexMap<String, JsonValue>,
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for Schema<'a> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>
+    {
+        deserializer.deserialize_any(SchemaVisitor(PhantomData))
+    }
+}
+
+// Since draft 6 true and false are schemas too, the ones everything and nothing is valid against
+struct SchemaVisitor<'a>(PhantomData<Schema<'a>>);
+
+impl<'de: 'a, 'a> Visitor<'de> for SchemaVisitor<'a> {
+    type Value = Schema<'a>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a schema or a boolean")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E> {
+        match value {
+            true => Ok(Schema::default()),
+            false => Ok(Schema::nothing()),
+        }
+    }
+
+    fn visit_map<M>(self, map: M) -> Result<Self::Value, M::Error>
+    where
+        M: MapAccess<'de>
+    {
+        let mut schema = Schema::deserialize(MapAccessDeserializer::new(map))?;
+
+        // Keywords earlier drafts spelled differently
+        schema.exclusive_minimum = match schema.exclusive_minimum_keyword.take() {
+            Some(Bound::Number(bound)) => Some(bound),
+            Some(Bound::Flag(true)) => schema.minimum.take(),
+            Some(Bound::Flag(false)) | None => None,
+        };
+        schema.exclusive_maximum = match schema.exclusive_maximum_keyword.take() {
+            Some(Bound::Number(bound)) => Some(bound),
+            Some(Bound::Flag(true)) => schema.maximum.take(),
+            Some(Bound::Flag(false)) | None => None,
+        };
+
+        match schema.items_keyword.take() {
+            Some(Items::Schema(items)) => schema.items = Some(items),
+            Some(Items::Tuple(prefix_items)) => {
+                schema.prefix_items = prefix_items;
+                schema.items = match schema.additional_items.take() {
+                    Some(AdditionalProperties::Schema(items)) => Some(items),
+                    Some(AdditionalProperties::Boolean(false)) => Some(Box::new(Schema::nothing())),
+                    Some(AdditionalProperties::Boolean(true)) | None => None,
+                };
+            },
+            None => {},
+        }
+        // additionalItems does nothing without an array of items
+        schema.additional_items = None;
+
+        for (trigger, dependency) in std::mem::take(&mut schema.dependencies) {
+            match dependency {
+                Dependency::Required(required) => schema.dependent_required.entry(trigger).or_default().extend(required),
+                Dependency::Schema(dependent) => {
+                    schema.dependent_schemas.insert(trigger, *dependent);
+                },
+            }
+        }
+
+        Ok(schema)
+    }
+}
+
+impl<'a> Schema<'a> {
+    /// The schema no value is valid against, `false` in later drafts
+    pub fn nothing() -> Self {
+        Self {
+            not: Some(Box::default()),
+            ..Default::default()
+        }
+    }
+
+    // Schemas with a single keyword, for building schemas up from something other than JSON Schema (see openapi.rs)
+    pub(crate) fn reference_to<R: Into<Cow<'a, str>>>(reference: R) -> Self {
+        Self {
+            reference: Some(reference.into()),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn constant_of(value: JsonValue) -> Self {
+        Self {
+            constant: Some(value),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn of_type(instance_type: &str) -> Self {
+        Self {
+            instance_type: vec![instance_type.to_string()],
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn with_definitions(definitions: IndexMap<String, Schema<'a>>) -> Self {
+        Self {
+            definitions,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn any_of(alternatives: Vec<Schema<'a>>) -> Self {
+        Self {
+            any_of: alternatives,
+            ..Default::default()
+        }
+    }
+
+    /// Whether this is `false` or something equivalent, `{"not": {}}` give or take annotations such as `$schema`
+    pub fn is_nothing(&self) -> bool {
+        if self.not.as_deref() != Some(&Self::default()) {
+            return false
+        }
+
+        let constraints = Self {
+            unknown: IndexMap::new(),
+            ..self.constraints()
+        };
+
+        constraints == Self::nothing()
+    }
+
+    // The schema without the keywords that only describe or identify it, so what's left is what a value has to do
+    pub(crate) fn constraints(&self) -> Self {
+        Self {
+            id: None,
+            schema_uri: None,
+            anchor: None,
+            dynamic_anchor: None,
+            recursive_anchor: None,
+            description: None,
+            title: None,
+            default: None,
+            definitions: IndexMap::new(),
+            unknown: self.unknown.iter()
+                .filter(|(keyword, _)| !ANNOTATIONS.contains(&keyword.as_str()) && !keyword.starts_with("x-"))
+                .map(|(keyword, value)| (keyword.clone(), value.clone()))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// The draft `$schema` says this is
+    pub fn draft(&self) -> Option<Draft> {
+        self.schema_uri.as_deref().and_then(Draft::from_uri)
+    }
+
+    /// This schema with `$ref` replacing everything next to it, apart from annotations and the definitions it may
+    /// well refer to, the way drafts up to 7 read it
+    pub fn without_ref_siblings(&self) -> Self {
+        let mut schema = match self.reference.as_ref() {
+            Some(reference) => Self {
+                id: self.id.clone(),
+                reference: Some(reference.clone()),
+                schema_uri: self.schema_uri.clone(),
+                description: self.description.clone(),
+                title: self.title.clone(),
+                definitions: self.definitions.clone(),
+                ..Default::default()
+            },
+            None => self.clone(),
+        };
+
+        let mut pending = vec![&mut schema];
+        while let Some(schema) = pending.pop() {
+            for subschema in schema.subschemas_mut() {
+                if subschema.reference.is_some() {
+                    *subschema = subschema.without_ref_siblings();
+                } else {
+                    pending.push(subschema);
+                }
+            }
+        }
+
+        schema
+    }
+
+    /// This schema with every reference resolved statically, with this schema as the root, into a JSON pointer in
+    /// `$ref`.  That's `$ref`s to plain-name fragments like `#node`, which name an `$anchor` (or a draft 6/7 `$id`
+    /// such as `#node`), as well as `$dynamicRef` and `$recursiveRef`.  Dynamic references go to the outermost
+    /// `$dynamicAnchor` of that name, which is where they end up as long as nothing extends this schema from outside,
+    /// and `$recursiveRef` to the root.  References that can't be resolved are left as they are.
+    pub fn with_static_references(&self) -> Cow<'_, Schema<'a>> {
+        let mut anchors : HashMap<String, String> = HashMap::new();
+        let mut dynamic_anchors : HashMap<String, String> = HashMap::new();
+        let mut resolvable = false;
+
+        // Breadth first, so the outermost anchor of a name is the one that counts
+        let mut pending = VecDeque::from([(String::from("#"), self)]);
+        while let Some((location, schema)) = pending.pop_front() {
+            let plain_name = |reference: &Cow<str>| reference.starts_with('#') && !reference.starts_with("#/") && reference.len() > 1;
+            resolvable |= schema.reference.as_ref().is_some_and(plain_name) || schema.dynamic_reference.is_some() || schema.recursive_reference.is_some();
+
+            let id_anchor = schema.id.as_deref().and_then(|id| id.strip_prefix('#'));
+            for anchor in schema.anchor.as_deref().into_iter().chain(id_anchor).chain(schema.dynamic_anchor.as_deref()) {
+                anchors.entry(anchor.to_string()).or_insert_with(|| location.clone());
+            }
+            if let Some(anchor) = schema.dynamic_anchor.as_deref() {
+                dynamic_anchors.entry(anchor.to_string()).or_insert_with(|| location.clone());
+            }
+
+            pending.extend(schema.located_subschemas().into_iter().map(|(path, subschema)| (format!("{}/{}", location, path), subschema)));
+        }
+
+        if !resolvable {
+            return Cow::Borrowed(self)
+        }
+
+        let resolve = |reference: &str, dynamic: bool| -> Option<String> {
+            let name = reference.strip_prefix('#').filter(|name| !name.is_empty() && !name.starts_with('/'))?;
+            match dynamic {
+                true => dynamic_anchors.get(name).or_else(|| anchors.get(name)).cloned(),
+                false => anchors.get(name).cloned(),
+            }
+        };
+
+        let mut resolved = self.clone();
+        let mut pending = vec![&mut resolved];
+        while let Some(schema) = pending.pop() {
+            if let Some(pointer) = schema.reference.as_deref().and_then(|reference| resolve(reference, false)) {
+                schema.reference = Some(Cow::Owned(pointer));
+            }
+
+            let dynamic = schema.dynamic_reference.take().map(|reference| resolve(&reference, true).map(Cow::Owned).unwrap_or(reference))
+                .or_else(|| schema.recursive_reference.take());
+            if let Some(reference) = dynamic {
+                // $ref may be there too, in which case both apply
+                match schema.reference.is_some() {
+                    true => schema.all_of.push(Self { reference: Some(reference), ..Default::default() }),
+                    false => schema.reference = Some(reference),
+                }
+            }
+
+            pending.extend(schema.subschemas_mut());
+        }
+
+        Cow::Owned(resolved)
+    }
+
+    /// The schemas nested directly inside this one, not counting `definitions`
+    pub fn subschemas(&self) -> Vec<&Schema<'a>> {
+        self.located_subschemas().into_iter()
+            .filter(|(location, _)| !location.starts_with("definitions/"))
+            .map(|(_, schema)| schema)
+            .collect()
+    }
+
+    /// The schemas nested directly inside this one, counting `definitions`, and the JSON pointer from this one to
+    /// each of them, such as `properties/foo`
+    pub fn located_subschemas(&self) -> Vec<(String, &Schema<'a>)> {
+        fn token(token: &str) -> String {
+            token.replace('~', "~0").replace('/', "~1")
+        }
+
+        let mut subschemas = Vec::new();
+
+        subschemas.extend(self.definitions.iter().map(|(name, schema)| (format!("definitions/{}", token(name)), schema)));
+        if let Some(AdditionalProperties::Schema(schema)) = self.additional_properties.as_ref() {
+            subschemas.push((String::from("additionalProperties"), schema.as_ref()));
+        }
+        subschemas.extend(self.properties.iter().map(|(name, schema)| (format!("properties/{}", token(name)), schema)));
+        subschemas.extend(self.pattern_properties.iter().map(|(pattern, schema)| (format!("patternProperties/{}", token(pattern)), schema)));
+        subschemas.extend(self.dependent_schemas.iter().map(|(trigger, schema)| (format!("dependentSchemas/{}", token(trigger)), schema)));
+        subschemas.extend(self.all_of.iter().enumerate().map(|(i, schema)| (format!("allOf/{}", i), schema)));
+        subschemas.extend(self.any_of.iter().enumerate().map(|(i, schema)| (format!("anyOf/{}", i), schema)));
+        subschemas.extend(self.one_of.iter().enumerate().map(|(i, schema)| (format!("oneOf/{}", i), schema)));
+        subschemas.extend(self.prefix_items.iter().enumerate().map(|(i, schema)| (format!("prefixItems/{}", i), schema)));
+
+        let keywords = [("items", &self.items), ("if", &self.if_schema), ("then", &self.then_schema), ("else", &self.else_schema), ("not", &self.not)];
+        subschemas.extend(keywords.iter().filter_map(|(keyword, schema)| Some((keyword.to_string(), schema.as_deref()?))));
+
+        subschemas
+    }
+
+    // Like subschemas, but mutable and counting definitions
+    pub(crate) fn subschemas_mut(&mut self) -> Vec<&mut Schema<'a>> {
+        let mut subschemas = Vec::new();
+
+        if let Some(AdditionalProperties::Schema(schema)) = self.additional_properties.as_mut() {
+            subschemas.push(schema.as_mut());
+        }
+        subschemas.extend(self.definitions.values_mut());
+        subschemas.extend(self.properties.values_mut());
+        subschemas.extend(self.pattern_properties.values_mut());
+        subschemas.extend(self.dependent_schemas.values_mut());
+        subschemas.extend(self.all_of.iter_mut());
+        subschemas.extend(self.any_of.iter_mut());
+        subschemas.extend(self.one_of.iter_mut());
+        subschemas.extend(self.prefix_items.iter_mut());
+
+        for schema in [&mut self.items, &mut self.if_schema, &mut self.then_schema, &mut self.else_schema, &mut self.not] {
+            if let Some(schema) = schema.as_deref_mut() {
+                subschemas.push(schema);
+            }
+        }
+
+        subschemas
+    }
+
+    /// The schema a JSON pointer such as `#/definitions/Foo/properties/bar` refers to, `#` being this one.  Anything
+    /// else, like an anchor (`#foo`) or another document (`other.json#/definitions/foo`), isn't found.
+    pub fn pointer(&self, pointer: &str) -> Option<&Schema<'a>> {
+        let pointer = match pointer {
+            "" | "#" => return Some(self),
+            pointer => pointer.strip_prefix("#/")?,
+        };
+        let mut tokens = pointer.split('/').map(|token| token.replace("~1", "/").replace("~0", "~")).peekable();
+        let mut schema = self;
+
+        while let Some(token) = tokens.next() {
+            schema = match token.as_str() {
+                "definitions" | "$defs" => schema.definitions.get(&tokens.next()?)?,
+                "properties" => schema.properties.get(tokens.next()?.as_str())?,
+                "patternProperties" => schema.pattern_properties.get(&tokens.next()?)?,
+                "dependentSchemas" => schema.dependent_schemas.get(&tokens.next()?)?,
+                "allOf" => schema.all_of.get(tokens.next()?.parse::<usize>().ok()?)?,
+                "anyOf" => schema.any_of.get(tokens.next()?.parse::<usize>().ok()?)?,
+                "oneOf" => schema.one_of.get(tokens.next()?.parse::<usize>().ok()?)?,
+                "additionalProperties" => match schema.additional_properties.as_ref()? {
+                    AdditionalProperties::Schema(schema) => schema,
+                    AdditionalProperties::Boolean(_) => return None,
+                },
+                "prefixItems" => schema.prefix_items.get(tokens.next()?.parse::<usize>().ok()?)?,
+                // Before 2020-12 an index into items is one of what are now prefixItems, and additionalItems what
+                // are now items
+                "items" if tokens.peek().is_some_and(|index| index.parse::<usize>().is_ok()) => {
+                    schema.prefix_items.get(tokens.next()?.parse::<usize>().ok()?)?
+                },
+                "items" | "additionalItems" => schema.items.as_deref()?,
+                "if" => schema.if_schema.as_deref()?,
+                "then" => schema.then_schema.as_deref()?,
+                "else" => schema.else_schema.as_deref()?,
+                "not" => schema.not.as_deref()?,
+                _ => return None,
+            };
+        }
+
+        Some(schema)
+    }
 }
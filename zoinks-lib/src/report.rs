@@ -0,0 +1,143 @@
+use crate::generator::{Diagnostic, Options, genimpl_with_options, untyped_value};
+use crate::parser::{ANNOTATIONS, AdditionalProperties, Schema};
+
+/// Every keyword in `schema` that was read but that the generated types don't enforce, located by JSON pointer, e.g.
+/// `#/definitions/Color/pattern`, followed by the generator's own diagnostics, which are located by the generated type.
+/// Annotations such as `examples` and vendor extensions (`x-` keywords) aren't listed.
+pub fn unsupported_keywords(schema: &Schema) -> Vec<Diagnostic> {
+    unsupported_keywords_with_options(schema, &Options::default())
+}
+
+/// Like `unsupported_keywords`, for the types `genimpl_with_options` generates
+pub fn unsupported_keywords_with_options(schema: &Schema, options: &Options) -> Vec<Diagnostic> {
+    let mut report = Vec::new();
+    check(schema, "#", false, false, &mut report);
+    report.extend(genimpl_with_options(schema, options).1);
+
+    report
+}
+
+// With `Options::strict`, anything that won't be enforced is an error
+pub(crate) fn check_strict(schema: &Schema, options: &Options) -> Result<(), Vec<Diagnostic>> {
+    let unsupported = match options.strict {
+        true => unsupported_keywords_with_options(schema, options),
+        false => Vec::new(),
+    };
+
+    match unsupported.is_empty() {
+        true => Ok(()),
+        false => Err(unsupported),
+    }
+}
+
+// `condition` is whether this is inside an if schema, where const and enum pick a branch rather than constrain, and
+// `merged` whether this is a then, else or dependent schema, which is generated as part of its parent
+fn check(schema: &Schema, location: &str, condition: bool, merged: bool, report: &mut Vec<Diagnostic>) {
+    let mut unsupported = |keyword: &str, message: &str| report.push(Diagnostic {
+        location: format!("{}/{}", location, keyword.replace('~', "~0").replace('/', "~1")),
+        message: message.into(),
+    });
+
+    for keyword in schema.unknown.keys().filter(|keyword| !ANNOTATIONS.contains(&keyword.as_str()) && !keyword.starts_with("x-")) {
+        unsupported(keyword, "Unknown keyword, it is ignored");
+    }
+
+    // Nothing says what this is, so it's generated as serde_json::Value and whatever it constrains goes unchecked.  Its
+    // subschemas are covered by the keywords they belong to, apart from definitions.
+    if untyped_value(schema) && !condition && !merged {
+        for keyword in constraints(schema) {
+            unsupported(keyword, "The schema has no type, so it becomes serde_json::Value and this is not enforced");
+        }
+
+        for (path, subschema) in schema.located_subschemas().into_iter().filter(|(path, _)| path.starts_with("definitions/")) {
+            check(subschema, &format!("{}/{}", location, path), condition, false, report);
+        }
+        return
+    }
+
+    if schema.pattern.is_some() {
+        unsupported("pattern", "Patterns are not enforced");
+    }
+    if schema.format.is_some() {
+        unsupported("format", "Formats are not enforced");
+    }
+    if !schema.pattern_properties.is_empty() {
+        unsupported("patternProperties", "Pattern properties are not enforced");
+    }
+    if let Some(AdditionalProperties::Schema(_)) = schema.additional_properties {
+        unsupported("additionalProperties", "Only false is enforced, other properties are ignored");
+    }
+    if schema.multiple_of.is_some() {
+        unsupported("multipleOf", "Multiples are not enforced");
+    }
+    if schema.unique_items == Some(true) {
+        unsupported("uniqueItems", "Unique items are not enforced");
+    }
+    if schema.constant.is_some() && !condition {
+        unsupported("const", "Constants are only enforced in if schemas");
+    }
+    if !schema.any_of.is_empty() && !schema.instance_type.is_empty() {
+        unsupported("anyOf", "anyOf is only enforced on schemas without a type");
+    }
+    if !schema.all_of.is_empty() && !schema.all_of.iter().any(Schema::is_nothing) {
+        unsupported("allOf", "allOf is not enforced");
+    }
+    if !schema.one_of.is_empty() {
+        unsupported("oneOf", "oneOf is not enforced");
+    }
+
+    // Tuples have a fixed length, which is the only way the length of an array is enforced
+    let tuple = !schema.prefix_items.is_empty()
+        && schema.min_items == Some(schema.prefix_items.len() as u32) && schema.max_items == Some(schema.prefix_items.len() as u32);
+    if schema.min_items.is_some() && !tuple {
+        unsupported("minItems", "Array lengths are only enforced for tuples");
+    }
+    if schema.max_items.is_some() && !tuple {
+        unsupported("maxItems", "Array lengths are only enforced for tuples");
+    }
+
+    for (path, subschema) in schema.located_subschemas() {
+        // Reported as a whole above, or by the generator in the case of not
+        let keyword = path.split('/').next().unwrap_or_default();
+        if ["patternProperties", "additionalProperties", "allOf", "oneOf", "not"].contains(&keyword)
+            || (keyword == "anyOf" && !schema.instance_type.is_empty()) {
+            continue
+        }
+
+        let condition = condition || path == "if";
+        let merged = ["then", "else", "dependentSchemas"].contains(&keyword);
+        check(subschema, &format!("{}/{}", location, path), condition, merged, report);
+    }
+}
+
+// The keywords of a schema that constrain what it describes
+fn constraints(schema: &Schema) -> Vec<&'static str> {
+    let keywords = [
+        ("minLength", schema.min_length.is_some()),
+        ("maxLength", schema.max_length.is_some()),
+        ("pattern", schema.pattern.is_some()),
+        ("format", schema.format.is_some()),
+        ("minimum", schema.minimum.is_some()),
+        ("maximum", schema.maximum.is_some()),
+        ("exclusiveMinimum", schema.exclusive_minimum.is_some()),
+        ("exclusiveMaximum", schema.exclusive_maximum.is_some()),
+        ("multipleOf", schema.multiple_of.is_some()),
+        ("items", schema.items.is_some()),
+        ("prefixItems", !schema.prefix_items.is_empty()),
+        ("minItems", schema.min_items.is_some()),
+        ("maxItems", schema.max_items.is_some()),
+        ("uniqueItems", schema.unique_items == Some(true)),
+        ("required", !schema.required.is_empty()),
+        ("additionalProperties", schema.additional_properties.is_some()),
+        ("patternProperties", !schema.pattern_properties.is_empty()),
+        ("minProperties", schema.min_properties.is_some()),
+        ("maxProperties", schema.max_properties.is_some()),
+        ("dependentRequired", !schema.dependent_required.is_empty()),
+        ("dependentSchemas", !schema.dependent_schemas.is_empty()),
+        ("const", schema.constant.is_some()),
+        ("allOf", !schema.all_of.is_empty()),
+        ("oneOf", !schema.one_of.is_empty()),
+    ];
+
+    keywords.iter().filter(|(_, present)| *present).map(|(keyword, _)| *keyword).collect()
+}
@@ -1,3 +1,9 @@
+// The generated code as a token string, which is easier to match against than the formatted output
+fn tokens(schema: &str) -> String {
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    crate::genimpl(&schema).to_string()
+}
+
 #[test]
 fn address_schema() {
     let schema = include_str!("../../schema-examples/address.schema.json");
@@ -33,3 +39,648 @@ fn vega_lite_schema() {
     let schema = include_str!("../../schema-examples/vega-lite-v5.schema.json");
     crate::schema2print(schema);
 }
+
+#[test]
+fn dependent_schemas() {
+    let schema = r#"{
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "credit_card": { "type": "number" },
+            "billing_address": { "type": "string" }
+        },
+        "dependentSchemas": {
+            "credit_card": {
+                "properties": {
+                    "billing_address": { "type": "string", "minLength": 1 }
+                },
+                "required": ["billing_address"]
+            }
+        }
+    }"#;
+    let out = tokens(schema);
+    assert!(out.contains("pub struct TopLevelDpndntCreditCard"), "{}", out);
+    assert!(out.contains(r#"# [serde (remote = "Self")]"#), "{}", out);
+}
+
+#[test]
+fn conditional_discriminator() {
+    let schema = r#"{
+        "type": "object",
+        "properties": {
+            "kind": { "type": "string" }
+        },
+        "required": ["kind"],
+        "if": { "properties": { "kind": { "const": "circle" } } },
+        "then": { "properties": { "radius": { "type": "number" } }, "required": ["radius"] },
+        "else": {
+            "if": { "properties": { "kind": { "const": "rect" } } },
+            "then": { "properties": { "width": { "type": "number" } }, "required": ["width"] }
+        }
+    }"#;
+    let out = tokens(schema);
+    assert!(out.contains("pub enum TopLevel { Circle (TopLevelCircle) , Rect (TopLevelRect) , Other (TopLevelOther) }"), "{}", out);
+}
+
+#[test]
+fn conditional_union() {
+    let schema = r#"{
+        "type": "object",
+        "properties": {
+            "country": { "type": "string" }
+        },
+        "if": { "properties": { "country": { "enum": ["US", "CA"] } }, "required": ["country"] },
+        "then": { "properties": { "postal_code": { "type": "string", "minLength": 5 } } },
+        "else": { "properties": { "postal_code": { "type": "string", "maxLength": 3 } } }
+    }"#;
+    let out = tokens(schema);
+    assert!(out.contains("pub struct TopLevelThen"), "{}", out);
+    assert!(out.contains("pub struct TopLevelElse"), "{}", out);
+    assert!(out.contains("pub postal_code : Option < serde_json :: Value >"), "{}", out);
+}
+
+#[test]
+fn not_schemas() {
+    let schema = r#"{
+        "definitions": {
+            "signalName": { "type": "string", "not": { "enum": ["parent", "datum"] } },
+            "notNull": { "not": { "type": "null" } },
+            "noLeadingA": { "type": "string", "not": { "pattern": "^a" } },
+            "odd": { "type": "number", "not": { "type": "number", "multipleOf": 2 } }
+        }
+    }"#;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    let (out, diagnostics) = crate::genimpl_with_diagnostics(&schema);
+    let out = out.to_string();
+    assert!(out.contains("pub struct SignalName (String)"), "{}", out);
+    assert!(out.contains("pub struct NotNull (serde_json :: Value)"), "{}", out);
+    assert!(diagnostics.iter().any(|diagnostic| diagnostic.location == "NoLeadingA"), "{:?}", diagnostics);
+    assert!(diagnostics.iter().any(|diagnostic| diagnostic.location == "Odd"), "{:?}", diagnostics);
+    assert!(out.contains("pub struct Odd (f64)"), "{}", out);
+}
+
+#[test]
+fn enums_of_every_kind() {
+    let schema = r#"{
+        "definitions": {
+            "autosize": { "enum": ["pad", "fit", "none"] },
+            "mixed": { "enum": ["a", true, null, [1, 2], { "k": "v" }] }
+        }
+    }"#;
+    let out = tokens(schema);
+    assert!(out.contains("pub enum Mixed { A , True , Null , Array3 , Object4 }"), "{}", out);
+}
+
+#[test]
+fn numeric_enums() {
+    let schema = r#"{
+        "definitions": {
+            "nums": { "type": "number", "enum": [1, 1.0, -2, 0.5, -0.5] }
+        }
+    }"#;
+    let out = tokens(schema);
+    assert!(out.contains("pub enum Nums { N1 , Neg2 , N0_5 , Neg0_5 }"), "{}", out);
+}
+
+#[test]
+fn multiple_types() {
+    let schema = r#"{
+        "definitions": {
+            "maybeThing": { "type": ["object", "null"], "properties": { "a": { "type": "string" } } },
+            "listOrName": { "type": ["string", "array"], "items": { "type": "string" }, "maxLength": 8 },
+            "count": { "type": ["null", "number", "integer"], "minimum": 0 }
+        }
+    }"#;
+    let out = tokens(schema);
+    assert!(out.contains("pub type MaybeThing = Option < MaybeThingObject >"), "{}", out);
+    assert!(out.contains("pub struct MaybeThingObject {"), "{}", out);
+    assert!(out.contains("pub enum ListOrName { String (ListOrNameString) , Array (ListOrNameArray) }"), "{}", out);
+    assert!(out.contains("pub type ListOrNameArray = Vec < ListOrNameArrayItem >"), "{}", out);
+    assert!(out.contains("pub enum CountValue { Integer (CountInteger) , Number (CountNumber) }"), "{}", out);
+    assert!(out.contains("pub type Count = Option < CountValue >"), "{}", out);
+    assert!(out.contains("zoinks_support :: IntegerValidatorConfig"), "{}", out);
+}
+
+#[test]
+fn nullable_types() {
+    let schema = r##"{
+        "definitions": {
+            "name": { "type": ["string", "null"] },
+            "axis": { "type": "object", "properties": { "title": { "type": "string" } } },
+            "chart": {
+                "type": "object",
+                "properties": {
+                    "axis": { "anyOf": [{ "$ref": "#/definitions/axis" }, { "type": "null" }] },
+                    "name": { "$ref": "#/definitions/name" },
+                    "label": { "anyOf": [{ "type": "string" }, { "type": "number" }, { "type": "null" }] }
+                },
+                "required": ["name"]
+            }
+        }
+    }"##;
+    let out = tokens(schema);
+    assert!(out.contains("pub axis : zoinks_support :: Nullable < Axis >"), "{}", out);
+}
+
+#[test]
+fn output_is_deterministic() {
+    for schema in &[include_str!("../../schema-examples/vega-v5.schema.json"), include_str!("../../schema-examples/vega-lite-v5.schema.json")] {
+        let expected = tokens(schema);
+        assert_eq!(tokens(schema), expected);
+
+        let mut reordered = serde_json::from_str::<crate::Schema>(schema).unwrap();
+        reordered.definitions = reordered.definitions.into_iter().rev().collect();
+        assert_eq!(crate::genimpl(&reordered).to_string(), expected);
+    }
+}
+
+#[test]
+fn formatted_output() {
+    let out = crate::schemafile2print("../schema-examples/card.schema.json");
+    let mut lines = out.lines();
+    assert_eq!(lines.next(), Some(concat!("// Generated by zoinks ", env!("CARGO_PKG_VERSION"), " from ../schema-examples/card.schema.json, do not edit.")));
+    assert_eq!(lines.next(), Some(""));
+    assert!(out.contains("\n#[allow(non_camel_case_types, clippy::all)]\n#[derive(Debug, serde::Deserialize)]\npub struct TopLevelPrptyFn(String);\n"), "{}", out);
+    assert!(!out.contains("#!["), "{}", out);
+}
+
+#[test]
+fn split_into_modules() {
+    let out_dir = std::env::temp_dir().join(format!("zoinks-split-{}", std::process::id()));
+    crate::schemafile2dir("../schema-examples/vega-lite-v5.schema.json", &out_dir, &crate::Options { grouping: crate::Grouping::FirstWord, ..Default::default() }).unwrap();
+
+    let mod_rs = std::fs::read_to_string(out_dir.join("mod.rs")).unwrap();
+    assert!(mod_rs.contains("mod axis;\n#[allow(unused_imports)]\npub use self::axis::*;\n"), "{}", mod_rs);
+    assert!(mod_rs.contains("pub struct Null;"), "{}", mod_rs);
+    assert!(mod_rs.contains("pub struct TopLevel("), "{}", mod_rs);
+
+    let axis = std::fs::read_to_string(out_dir.join("axis.rs")).unwrap();
+    assert!(axis.contains("use super::*;"), "{}", axis);
+    assert!(axis.contains("pub struct AxisConfig {"), "{}", axis);
+    assert!(!axis.contains("pub struct Null;"), "{}", axis);
+
+    std::fs::remove_dir_all(out_dir).unwrap();
+}
+
+#[test]
+fn roots() {
+    let schema = r##"{
+        "type": "object",
+        "properties": { "a": { "$ref": "#/definitions/a" } },
+        "definitions": {
+            "a": { "type": "object", "properties": { "b": { "$ref": "#/definitions/b" } } },
+            "b": { "type": "array", "items": { "$ref": "#/definitions/c~1d" } },
+            "c/d": { "type": "string" },
+            "unused": { "type": "string" }
+        }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+
+    let options = crate::Options { roots: vec![String::from("#/definitions/a")], ..Default::default() };
+    let (out, diagnostics) = crate::genimpl_with_options(&schema, &options);
+    let out = out.to_string();
+    assert!(out.contains("pub struct A {"), "{}", out);
+    assert!(out.contains("pub type B ="), "{}", out);
+    assert!(out.contains("pub struct CD (String)"), "{}", out);
+    assert!(!out.contains("Unused"), "{}", out);
+    assert!(!out.contains("TopLevel"), "{}", out);
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+    let options = crate::Options { roots: vec![String::from("#"), String::from("missing")], ..Default::default() };
+    let (out, diagnostics) = crate::genimpl_with_options(&schema, &options);
+    let out = out.to_string();
+    assert!(out.contains("pub struct TopLevel {"), "{}", out);
+    assert!(out.contains("pub struct A {"), "{}", out);
+    assert!(!out.contains("Unused"), "{}", out);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+}
+
+#[test]
+fn merge_identical() {
+    let schema = r##"{
+        "definitions": {
+            "a": {
+                "type": "object",
+                "properties": {
+                    "opacity": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "point": { "type": "object", "properties": { "x": { "type": "number" } } }
+                }
+            },
+            "b": {
+                "type": "object",
+                "properties": {
+                    "alpha": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "point": { "type": "object", "properties": { "x": { "type": "number" } } }
+                }
+            },
+            "unit": { "type": "number", "minimum": 0, "maximum": 1 },
+            "fraction": { "type": "number", "minimum": 0, "maximum": 1 }
+        }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+
+    let options = crate::Options { merge: crate::Merge::Identical, ..Default::default() };
+    let out = crate::genimpl_with_options(&schema, &options).0.to_string();
+    assert!(out.contains("pub alpha : Option < Fraction >"), "{}", out);
+    assert_eq!(crate::genmodules(&schema, &options).merged, 4);
+
+    let options = crate::Options { merge: crate::Merge::IdenticalWithAliases, ..Default::default() };
+    let out = crate::genimpl_with_options(&schema, &options).0.to_string();
+    assert!(out.contains("pub type BPrptyAlpha = Fraction ;"), "{}", out);
+}
+
+#[test]
+fn config() {
+    let schema = r##"{
+        "type": "object",
+        "properties": {
+            "angle": { "$ref": "#/definitions/Angle" },
+            "color": { "$ref": "#/definitions/Color" },
+            "dataset": { "$ref": "#/definitions/Dataset" },
+            "label": { "type": "string", "maxLength": 10 }
+        },
+        "definitions": {
+            "Angle": { "anyOf": [{ "type": "number", "maximum": 360 }, { "$ref": "#/definitions/ExprRef" }] },
+            "Color": { "type": "string" },
+            "Dataset": { "type": "object", "properties": { "values": { "type": "array" } } },
+            "ExprRef": { "type": "object", "properties": { "expr": { "type": "string" } } }
+        }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+
+    let config = r##"{
+        "names": { "AngleNumber0": "Degrees", "#/definitions/ExprRef": "Expression", "#/properties/label": "Label" },
+        "types": { "#/definitions/Color": "crate::Color" },
+        "skip": ["Dataset", "#/definitions/Missing"]
+    }"##;
+    let options = crate::Options { config: serde_json::from_str(config).unwrap(), ..Default::default() };
+    let (out, diagnostics) = crate::genimpl_with_options(&schema, &options);
+    let out = out.to_string();
+    assert!(out.contains("pub enum Angle { Degrees (Degrees) , Expression (Expression) }"), "{}", out);
+    assert!(out.contains("pub struct Expression {"), "{}", out);
+    assert!(out.contains("pub struct Label (String)"), "{}", out);
+    assert!(out.contains("pub color : Option < crate :: Color >"), "{}", out);
+    assert!(!out.contains("pub struct Color"), "{}", out);
+    assert!(out.contains("pub dataset : Option < serde_json :: Value >"), "{}", out);
+    assert!(!out.contains("Dataset"), "{}", out);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+}
+
+#[test]
+fn config_pointers() {
+    let schema = r##"{
+        "$defs": {
+            "maybe": { "type": ["object", "null"], "properties": { "when": { "type": "string" }, "count": { "type": "integer" } } },
+            "skipped": { "type": "object", "properties": { "a": { "type": "string" } } }
+        }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+
+    // Each type of a multi-type schema is generated from a copy of it, which pointers have to find their way into
+    let config = r##"{
+        "names": { "#/$defs/maybe/properties/when": "When", "Nowhere": "Somewhere" },
+        "types": { "#/$defs/maybe/properties/count": "u8", "#/$defs/skipped/properties/a": "String" },
+        "skip": ["skipped"]
+    }"##;
+    let options = crate::Options { config: serde_json::from_str(config).unwrap(), ..Default::default() };
+    let (out, diagnostics) = crate::genimpl_with_options(&schema, &options);
+    let out = out.to_string();
+    assert!(out.contains("pub when : Option < When > , pub count : Option < u8 >"), "{}", out);
+
+    let locations = diagnostics.iter().map(|diagnostic| diagnostic.location.as_str()).collect::<Vec<_>>();
+    assert_eq!(locations, vec!["#/definitions/skipped/properties/a", "Nowhere"]);
+}
+
+#[test]
+fn pointers() {
+    let schema = r##"{ "type": "object", "definitions": { "x": { "type": "string" } } }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    assert_eq!(schema.pointer("#"), Some(&schema));
+    assert_eq!(schema.pointer("#/definitions/x"), Some(&schema.definitions["x"]));
+
+    // Anchors and other documents aren't JSON pointers into this one, so they mustn't land on the root or a lookalike
+    assert_eq!(schema.pointer("#missing"), None);
+    assert_eq!(schema.pointer("other.json#/definitions/x"), None);
+    assert_eq!(schema.pointer("definitions/x"), None);
+
+    let config = crate::Config { names: [(String::from("#TopLevel"), String::from("Root"))].iter().cloned().collect(), ..Default::default() };
+    let (out, diagnostics) = crate::genimpl_with_options(&schema, &crate::Options { config, ..Default::default() });
+    assert!(!out.to_string().contains("Root"), "{}", out);
+    assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+}
+
+#[test]
+fn empty_arrays_are_present() {
+    let schema = r##"{
+        "type": "object",
+        "minProperties": 1,
+        "properties": { "tags": { "type": "array" }, "name": { "type": "string" } },
+        "dependentRequired": { "name": ["tags"] }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+
+    // `{"tags": []}` has a property, so an optional Vec is an Option rather than empty when left out
+    let options = crate::Options { config: serde_json::from_str(r##"{ "types": { "#/properties/tags": "Vec<String>" } }"##).unwrap(), ..Default::default() };
+    let out = crate::genimpl_with_options(&schema, &options).0.to_string();
+    assert!(out.contains("pub tags : Option < Vec < String > >"), "{}", out);
+}
+
+#[test]
+fn validate() {
+    let schema = r##"{
+        "type": "object",
+        "required": ["mark"],
+        "additionalProperties": false,
+        "properties": {
+            "mark": { "enum": ["bar", "line"] },
+            "angle": { "$ref": "#/definitions/Angle" },
+            "tags": { "type": "array", "items": { "type": "string", "maxLength": 3 }, "uniqueItems": true },
+            "size": { "oneOf": [{ "type": "integer" }, { "type": "number", "minimum": 10 }] },
+            "data": { "if": { "required": ["url"] }, "then": { "required": ["format"] }, "else": { "required": ["values"] } },
+            "step": { "multipleOf": 0.1 },
+            "count": { "type": "integer", "multipleOf": 3 },
+            "missing": { "$ref": "#missing" }
+        },
+        "definitions": {
+            "Angle": { "anyOf": [{ "type": "number", "maximum": 360 }, { "$ref": "#/definitions/ExprRef" }] },
+            "ExprRef": { "type": "object", "required": ["expr"], "properties": { "expr": { "type": "string" } } }
+        }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+
+    let valid = serde_json::json!({ "mark": "bar", "angle": { "expr": "a" }, "tags": ["a", "b"], "size": 3, "data": { "values": [] }, "step": 0.3, "count": 9007199254740993_u64 });
+    assert_eq!(crate::validate(&schema, &valid), Ok(()));
+
+    let invalid = serde_json::json!({ "angle": 400, "tags": ["a", "abcd", "a"], "size": 12, "data": { "url": "x" }, "step": 0.35, "count": 4, "missing": 1, "extra": true });
+    let errors = crate::validate(&schema, &invalid).unwrap_err();
+    let errors = errors.iter().map(|error| (error.instance_path.as_str(), error.schema_path.as_str())).collect::<Vec<_>>();
+    assert_eq!(errors, vec![
+        ("", "#/required"),
+        ("", "#/additionalProperties"),
+        ("/angle", "#/properties/angle/$ref/anyOf"),
+        ("/count", "#/properties/count/multipleOf"),
+        ("/data", "#/properties/data/then/required"),
+        ("/missing", "#/properties/missing/$ref"),
+        ("/size", "#/properties/size/oneOf"),
+        ("/step", "#/properties/step/multipleOf"),
+        ("/tags", "#/properties/tags/uniqueItems"),
+        ("/tags/1", "#/properties/tags/items/maxLength"),
+    ]);
+
+    let error = crate::validate(&schema, &serde_json::json!({ "mark": "pie" })).unwrap_err().remove(0);
+    assert_eq!(error.to_string(), r#"/mark: invalid value: "pie", expected one of "bar", "line" (#/properties/mark/enum)"#);
+}
+
+#[test]
+fn drafts() {
+    let schema = r##"{
+        "$schema": "http://json-schema.org/draft-04/schema#",
+        "id": "https://example.com/draft4.json",
+        "type": "object",
+        "properties": {
+            "ratio": { "type": "number", "minimum": 0, "exclusiveMinimum": true, "maximum": 1 },
+            "point": { "type": "array", "items": [{ "type": "number" }, { "type": "number" }], "minItems": 2, "maxItems": 2 },
+            "billing": { "type": "string" }
+        },
+        "dependencies": { "card": ["billing"] }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    assert_eq!(schema.draft(), Some(crate::Draft::Draft4));
+    assert_eq!(schema.properties["ratio"].exclusive_minimum, Some(0.0));
+    assert_eq!(schema.properties["ratio"].minimum, None);
+    assert_eq!(schema.properties["point"].prefix_items.len(), 2);
+    assert_eq!(schema.dependent_required["card"], vec![String::from("billing")]);
+
+    let out = crate::genimpl(&schema).to_string();
+    assert!(out.contains("exclusive_min : Some (0f64)"), "{}", out);
+    assert!(out.contains("pub type TopLevelPrptyPoint = (TopLevelPrptyPointItem0 , TopLevelPrptyPointItem1 ,) ;"), "{}", out);
+
+    // Up to draft 7 $ref is all there is to a schema, afterwards the keywords next to it apply too
+    let schema = r##"{
+        "$defs": { "list": { "type": "array" } },
+        "properties": { "short": { "$ref": "#/$defs/list", "maxItems": 1 } }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    let instance = serde_json::json!({ "short": [1, 2] });
+    assert!(crate::validate(&schema, &instance).is_err());
+
+    let mut draft7 = schema.clone();
+    draft7.schema_uri = Some("http://json-schema.org/draft-07/schema#".into());
+    assert_eq!(crate::validate(&draft7, &instance), Ok(()));
+
+    let options = crate::Options { draft: Some(crate::Draft::Draft7), ..Default::default() };
+    let out = crate::genimpl_with_options(&schema, &options).0.to_string();
+    assert!(out.contains("pub short : Option < List >"), "{}", out);
+}
+
+#[test]
+fn boolean_schemas() {
+    let schema = r##"{
+        "type": "object",
+        "properties": {
+            "anything": true,
+            "removed": false,
+            "empty": { "type": "array", "items": false }
+        }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    assert!(schema.properties["removed"].is_nothing());
+    assert!(!schema.properties["anything"].is_nothing());
+
+    let out = crate::genimpl(&schema).to_string();
+    assert!(out.contains("pub struct TopLevelPrptyAnything (serde_json :: Value) ;"), "{}", out);
+    assert!(out.contains("pub enum TopLevelPrptyRemoved { }"), "{}", out);
+    assert!(out.contains("\"no value is valid here\""), "{}", out);
+
+    assert_eq!(crate::validate(&schema, &serde_json::json!({ "anything": 1, "empty": [] })), Ok(()));
+    let errors = crate::validate(&schema, &serde_json::json!({ "removed": 1 })).unwrap_err();
+    assert_eq!(errors[0].schema_path, "#/properties/removed");
+
+    let nothing = serde_json::from_str::<crate::Schema>("false").unwrap();
+    assert!(crate::validate(&nothing, &serde_json::json!(null)).is_err());
+}
+
+#[test]
+fn anchors_and_recursion() {
+    let schema = r##"{
+        "$dynamicAnchor": "node",
+        "type": "object",
+        "properties": {
+            "name": { "$ref": "#name" },
+            "parent": { "$dynamicRef": "#node" },
+            "children": { "type": "array", "items": { "$dynamicRef": "#node" } }
+        },
+        "$defs": {
+            "name": { "$anchor": "name", "type": "string" }
+        }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    let resolved = schema.with_static_references();
+    assert_eq!(resolved.properties["name"].reference.as_deref(), Some("#/definitions/name"));
+    assert_eq!(resolved.properties["parent"].reference.as_deref(), Some("#"));
+    assert_eq!(resolved.properties["parent"].dynamic_reference, None);
+
+    // Only the field that would make TopLevel contain itself gets boxed, the Vec already has a pointer
+    let out = crate::genimpl(&schema).to_string();
+    assert!(out.contains("pub name : Option < Name >"), "{}", out);
+    assert!(out.contains("pub parent : Option < Box < TopLevel > >"), "{}", out);
+    assert!(out.contains("pub type TopLevelPrptyChildren = Vec < TopLevel > ;"), "{}", out);
+
+    let instance = serde_json::json!({ "name": "leaf", "parent": { "name": "root" }, "children": [{ "name": 1 }] });
+    let errors = crate::validate(&schema, &instance).unwrap_err();
+    assert_eq!(errors[0].instance_path, "/children/0/name");
+    assert_eq!(errors[0].schema_path, "#/properties/children/items/$ref/properties/name/$ref/type");
+}
+
+#[test]
+fn untyped_recursion() {
+    // Untyped schemas with properties are any type, with the struct for objects boxed where it refers back to itself
+    let schema = serde_json::from_str::<crate::Schema>(r##"{ "properties": { "foo": { "$ref": "#" } } }"##).unwrap();
+    let out = crate::genimpl(&schema).to_string();
+    assert!(out.contains("pub type TopLevel = Option < TopLevelValue > ;"), "{}", out);
+    assert!(out.contains("Object (TopLevelObject)"), "{}", out);
+    assert!(out.contains("pub foo : zoinks_support :: Nullable < Box < TopLevelValue > >"), "{}", out);
+
+    // There's nothing to box in a type alias
+    let schema = r##"{ "definitions": { "A": { "anyOf": [{ "type": "null" }, { "$ref": "#/definitions/A" }] } } }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    let (_, diagnostics) = crate::genimpl_with_diagnostics(&schema);
+    assert_eq!(diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>(), vec![
+        "A: Holds itself through type aliases alone, which can't be boxed, so it won't compile",
+    ]);
+}
+
+#[test]
+fn input_formats() {
+    use crate::Format;
+
+    assert_eq!(Format::from_path("openapi.yml"), Format::Yaml);
+    assert_eq!(Format::from_path("config.schema.JSON5"), Format::Json5);
+    assert_eq!(Format::from_path("vega-v5.schema.json"), Format::Json);
+    assert_eq!("yaml".parse(), Ok(Format::Yaml));
+
+    let e = Format::Json.parse("{\n  \"type\": \"object\",\n  \"properties\": []\n}").unwrap_err();
+    assert_eq!((e.line, e.column), (Some(3), Some(16)));
+    assert_eq!(e.to_string(), "invalid type: sequence, expected a map at line 3 column 16");
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml_input() {
+    let schema = "type: object\nproperties:\n  size: { type: integer, minimum: 0 }\n";
+    let schema = crate::Format::Yaml.parse(schema).unwrap();
+    assert_eq!(schema.properties["size"].minimum, Some(0.0));
+
+    let e = crate::Format::Yaml.parse("type: object\nproperties:\n  size: 3\n").unwrap_err();
+    assert_eq!((e.line, e.column), (Some(3), Some(9)));
+}
+
+#[cfg(feature = "json5")]
+#[test]
+fn json5_input() {
+    let schema = "// Sizes\n{ type: 'object', properties: { size: { type: 'integer', }, }, }";
+    let schema = crate::Format::Json5.parse(schema).unwrap();
+    assert_eq!(schema.properties["size"].instance_type, vec![String::from("integer")]);
+
+    let e = crate::Format::Json5.parse("{\n  type: 'object',\n  properties: { size: }\n}").unwrap_err();
+    assert_eq!((e.line, e.column), (Some(3), Some(23)));
+}
+
+#[test]
+fn openapi_components() {
+    let document = r##"{
+        "openapi": "3.0.3",
+        "info": { "title": "Pets", "version": "1" },
+        "paths": {},
+        "components": {
+            "schemas": {
+                "Pet": {
+                    "oneOf": [{ "$ref": "#/components/schemas/Cat" }, { "$ref": "#/components/schemas/Dog" }],
+                    "discriminator": { "propertyName": "kind", "mapping": { "dog": "#/components/schemas/Dog" } }
+                },
+                "Cat": {
+                    "type": "object",
+                    "required": ["kind", "id"],
+                    "properties": {
+                        "kind": { "type": "string" },
+                        "id": { "type": "integer", "readOnly": true }
+                    }
+                },
+                "Dog": {
+                    "type": "object",
+                    "required": ["kind"],
+                    "properties": {
+                        "kind": { "type": "string" },
+                        "owner": { "type": "string", "nullable": true, "example": "Shaggy" },
+                        "best_friend": { "$ref": "#/components/schemas/Pet", "nullable": true }
+                    }
+                }
+            }
+        }
+    }"##;
+    let schema = crate::from_openapi(document, crate::Format::Json).unwrap();
+    assert_eq!(schema.definitions["Cat"].required, vec![String::from("kind")]);
+    assert_eq!(schema.definitions["Dog"].properties["owner"].instance_type, vec![String::from("string"), String::from("null")]);
+    assert!(schema.definitions["Dog"].properties["owner"].unknown.is_empty());
+    assert_eq!(schema.definitions["Dog"].properties["best_friend"].any_of[0].reference.as_deref(), Some("#/definitions/Pet"));
+
+    // The discriminator picks the variant, each variant is the component it refers to, and other kinds are refused
+    let options = crate::Options { openapi: true, ..Default::default() };
+    let out = crate::genimpl_with_options(&schema, &options).0.to_string();
+    assert!(out.contains("pub enum Pet { Dog (Dog) , Cat (Cat) }"), "{}", out);
+    assert!(out.contains("expected it to match one of the if schemas"), "{}", out);
+    assert!(out.contains("pub id : Option < CatPrptyId >"), "{}", out);
+    assert!(out.contains("pub best_friend : zoinks_support :: Nullable < Box < Pet > >"), "{}", out);
+    assert!(!out.contains("TopLevel"), "{}", out);
+
+    let e = crate::from_openapi(r#"{ "swagger": "2.0", "openapi": "2.0" }"#, crate::Format::Json).unwrap_err();
+    assert_eq!(e.to_string(), "Only OpenAPI 3.0 and 3.1 documents are supported, this is 2.0");
+}
+
+#[test]
+fn unsupported_keywords() {
+    let schema = r##"{
+        "$comment": "Keywords zoinks doesn't model are kept rather than refused",
+        "x-generator": "hand written",
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "pattern": "^[a-z]+$", "examples": ["zoinks"] },
+            "tags": { "type": "array", "items": { "type": "string" }, "contains": { "const": "a" }, "uniqueItems": true },
+            "point": { "type": "array", "prefixItems": [{ "type": "number" }, { "type": "number" }], "minItems": 2, "maxItems": 2 }
+        },
+        "if": { "properties": { "kind": { "const": "circle" } } },
+        "then": { "required": ["radius"] }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    assert_eq!(schema.unknown["x-generator"], "hand written");
+    assert!(schema.properties["tags"].unknown.contains_key("contains"));
+
+    let report = crate::unsupported_keywords(&schema);
+    let locations = report.iter().map(|diagnostic| diagnostic.location.as_str()).collect::<Vec<_>>();
+    assert_eq!(locations, vec!["#/properties/name/pattern", "#/properties/tags/contains", "#/properties/tags/uniqueItems"]);
+
+    // Everything an untyped schema constrains is lost, and what the generator gives up on is reported too
+    let schema = r##"{
+        "type": "object",
+        "properties": {
+            "anything": { "maxLength": 2, "minimum": 3, "format": "email", "minProperties": 1 },
+            "odd": { "type": "number", "not": { "type": "number", "multipleOf": 2 } },
+            "pair": { "type": "array", "prefixItems": [{ "type": "number" }, { "type": "string" }] }
+        }
+    }"##;
+    let schema = serde_json::from_str::<crate::Schema>(schema).unwrap();
+    let report = crate::unsupported_keywords(&schema).iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(report, vec![
+        "#/properties/anything/maxLength: The schema has no type, so it becomes serde_json::Value and this is not enforced",
+        "#/properties/anything/format: The schema has no type, so it becomes serde_json::Value and this is not enforced",
+        "#/properties/anything/minimum: The schema has no type, so it becomes serde_json::Value and this is not enforced",
+        "#/properties/anything/minProperties: The schema has no type, so it becomes serde_json::Value and this is not enforced",
+        "TopLevelPrptyOdd: Unsupported not schema, it will not be enforced",
+        "TopLevelPrptyPair: Only tuples with a fixed number of items are supported, using Vec<serde_json::Value>",
+    ]);
+
+    let options = crate::Options { strict: true, ..Default::default() };
+    assert_eq!(crate::report::check_strict(&schema, &options).unwrap_err().len(), 6);
+    let supported = serde_json::from_str::<crate::Schema>(r#"{ "type": "string", "maxLength": 2 }"#).unwrap();
+    assert_eq!(crate::report::check_strict(&supported, &options), Ok(()));
+}
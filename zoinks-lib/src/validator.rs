@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use regex::Regex;
+use serde_json::Value as JsonValue;
+use zoinks_support::{NumericValidatorConfig, StringValidatorConfig, is_instance_of, json_eq};
+
+use crate::parser::{AdditionalProperties, Schema};
+
+/// A keyword a document failed, and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// JSON pointer to the value that failed, e.g. `/encoding/angle`.  Empty for the document itself.
+    pub instance_path: String,
+    /// JSON pointer to the keyword it failed, e.g. `#/definitions/Angle/maximum`
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.instance_path.is_empty() {
+            true => write!(f, "{} ({})", self.message, self.schema_path),
+            false => write!(f, "{}: {} ({})", self.instance_path, self.message, self.schema_path),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Checks `instance` against `schema`, collecting every failure rather than stopping at the first.  References are
+/// resolved against `schema` (see `Schema::with_static_references`), `$schema` decides what happens to keywords next
+/// to `$ref`, and `format` is treated as an annotation.
+pub fn validate(schema: &Schema, instance: &JsonValue) -> Result<(), Vec<ValidationError>> {
+    let schema = schema.with_static_references();
+    let mut validator = Validator {
+        root: &schema,
+        references: Vec::new(),
+        patterns: HashMap::new(),
+        ref_overrides_siblings: schema.draft().unwrap_or_default().ref_overrides_siblings(),
+    };
+
+    let errors = validator.check(&schema, "#", instance, "");
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+struct Validator<'s, 'a> {
+    root: &'s Schema<'a>,
+    // References being followed and the instance they're being followed for, so cycles that don't consume any of
+    // the instance end instead of recursing forever
+    references: Vec<(String, String)>,
+    // Every pattern the schema uses, compiled the first time it's needed, or None if it doesn't compile
+    patterns: HashMap<String, Option<Regex>>,
+    ref_overrides_siblings: bool,
+}
+
+// Appends a token to a JSON pointer, escaping it
+fn pointer(path: &str, token: &str) -> String {
+    format!("{}/{}", path, token.replace('~', "~0").replace('/', "~1"))
+}
+
+// Whether a number is a multiple of another.  Integers are divided exactly, anything else allows for the rounding
+// error in e.g. 0.3 / 0.1, which is 2.9999999999999996.
+fn is_multiple_of(instance: &JsonValue, multiple_of: f64) -> bool {
+    let whole = |n: f64| n.fract() == 0.0 && n.abs() < i64::MAX as f64;
+    match instance.as_i64() {
+        Some(n) if whole(multiple_of) && multiple_of != 0.0 => n % multiple_of as i64 == 0,
+        _ => {
+            let quotient = instance.as_f64().unwrap_or_default() / multiple_of;
+            (quotient - quotient.round()).abs() <= quotient.abs().max(1.0) * f64::EPSILON * 4.0
+        },
+    }
+}
+
+// What kind of value this is, in the terms of JSON Schema's `type`
+pub(crate) fn type_of(instance: &JsonValue) -> &'static str {
+    match instance {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(n) if n.as_f64().is_some_and(|n| n.fract() == 0.0) => "integer",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+impl<'s, 'a> Validator<'s, 'a> {
+    fn check(&mut self, schema: &Schema, schema_path: &str, instance: &JsonValue, instance_path: &str) -> Vec<ValidationError> {
+        if let (Some(reference), true) = (schema.reference.as_ref(), self.ref_overrides_siblings) {
+            return self.check_reference(reference, schema_path, instance, instance_path)
+        }
+
+        // false, which reads the same as {"not": {}}
+        if schema.is_nothing() {
+            return vec![ValidationError {
+                instance_path: instance_path.into(),
+                schema_path: schema_path.into(),
+                message: format!("invalid value: {}, no value is valid here", instance),
+            }]
+        }
+
+        let mut errors = Vec::new();
+        let mut fail = |keyword: &str, message: String| errors.push(ValidationError {
+            instance_path: instance_path.into(),
+            schema_path: pointer(schema_path, keyword),
+            message,
+        });
+
+        if !schema.instance_type.is_empty() && !schema.instance_type.iter().any(|instance_type| is_instance_of(instance, instance_type)) {
+            fail("type", format!("invalid type: {}, expected {}", type_of(instance), schema.instance_type.join(" or ")));
+        }
+
+        if !schema.enums.is_empty() && !schema.enums.iter().any(|member| json_eq(member, instance)) {
+            let members = schema.enums.iter().map(JsonValue::to_string).collect::<Vec<_>>();
+            fail("enum", format!("invalid value: {}, expected one of {}", instance, members.join(", ")));
+        }
+
+        if let Some(constant) = schema.constant.as_ref().filter(|constant| !json_eq(constant, instance)) {
+            fail("const", format!("invalid value: {}, expected {}", instance, constant));
+        }
+
+        if let Some(n) = instance.as_f64() {
+            let bounds = [
+                ("minimum", NumericValidatorConfig { min: schema.minimum, ..Default::default() }),
+                ("exclusiveMinimum", NumericValidatorConfig { exclusive_min: schema.exclusive_minimum, ..Default::default() }),
+                ("maximum", NumericValidatorConfig { max: schema.maximum, ..Default::default() }),
+                ("exclusiveMaximum", NumericValidatorConfig { exclusive_max: schema.exclusive_maximum, ..Default::default() }),
+            ];
+            for (keyword, bound) in bounds.iter().filter(|(_, bound)| !bound.valid(n)) {
+                fail(keyword, format!("invalid value: {}, expected {}", instance, bound));
+            }
+
+            if let Some(multiple_of) = schema.multiple_of.filter(|multiple_of| !is_multiple_of(instance, *multiple_of)) {
+                fail("multipleOf", format!("invalid value: {}, expected a multiple of {}", instance, multiple_of));
+            }
+        }
+
+        if let Some(s) = instance.as_str() {
+            let lengths = [
+                ("minLength", StringValidatorConfig { min_length: schema.min_length, max_length: None }),
+                ("maxLength", StringValidatorConfig { min_length: None, max_length: schema.max_length }),
+            ];
+            for (keyword, length) in lengths.iter().filter(|(_, length)| !length.valid(s)) {
+                fail(keyword, format!("invalid value: {:?}, expected {}", s, length));
+            }
+
+            if let Some(pattern) = schema.pattern.as_ref() {
+                match self.regex(pattern) {
+                    Some(regex) if regex.is_match(s) => {},
+                    Some(_) => fail("pattern", format!("invalid value: {:?}, expected a string matching {}", s, pattern)),
+                    None => fail("pattern", format!("unsupported pattern {}", pattern)),
+                }
+            }
+        }
+
+        if let Some(items) = instance.as_array() {
+            if let Some(min_items) = schema.min_items.filter(|min_items| items.len() < *min_items as usize) {
+                fail("minItems", format!("expected at least {} items, found {}", min_items, items.len()));
+            }
+
+            if let Some(max_items) = schema.max_items.filter(|max_items| items.len() > *max_items as usize) {
+                fail("maxItems", format!("expected at most {} items, found {}", max_items, items.len()));
+            }
+
+            if schema.unique_items == Some(true) {
+                let duplicate = (0..items.len()).find(|i| items[..*i].iter().any(|earlier| json_eq(earlier, &items[*i])));
+                if let Some(i) = duplicate {
+                    fail("uniqueItems", format!("expected unique items, item {} is a duplicate", i));
+                }
+            }
+        }
+
+        if let Some(object) = instance.as_object() {
+            for required in schema.required.iter().filter(|required| !object.contains_key(required.as_str())) {
+                fail("required", format!("missing property `{}`", required));
+            }
+
+            if let Some(min_properties) = schema.min_properties.filter(|min_properties| object.len() < *min_properties as usize) {
+                fail("minProperties", format!("expected at least {} properties, found {}", min_properties, object.len()));
+            }
+
+            if let Some(max_properties) = schema.max_properties.filter(|max_properties| object.len() > *max_properties as usize) {
+                fail("maxProperties", format!("expected at most {} properties, found {}", max_properties, object.len()));
+            }
+
+            for (trigger, dependents) in schema.dependent_required.iter().filter(|(trigger, _)| object.contains_key(trigger.as_str())) {
+                for dependent in dependents.iter().filter(|dependent| !object.contains_key(dependent.as_str())) {
+                    fail(&pointer("dependentRequired", trigger), format!("property `{}` is required when `{}` is present", dependent, trigger));
+                }
+            }
+
+            if let Some(AdditionalProperties::Boolean(false)) = schema.additional_properties {
+                for key in object.keys().filter(|key| !self.declared(schema, key)) {
+                    fail("additionalProperties", format!("unknown property `{}`", key));
+                }
+            }
+        }
+
+        // Everything from here on applies subschemas
+        if let Some(reference) = schema.reference.as_ref() {
+            errors.extend(self.check_reference(reference, schema_path, instance, instance_path));
+        }
+
+        if let Some(items) = instance.as_array() {
+            for (i, (item, prefix_item)) in items.iter().zip(schema.prefix_items.iter()).enumerate() {
+                errors.extend(self.check(prefix_item, &pointer(&pointer(schema_path, "prefixItems"), &i.to_string()), item, &pointer(instance_path, &i.to_string())));
+            }
+
+            if let Some(items_schema) = schema.items.as_deref() {
+                for (i, item) in items.iter().enumerate().skip(schema.prefix_items.len()) {
+                    errors.extend(self.check(items_schema, &pointer(schema_path, "items"), item, &pointer(instance_path, &i.to_string())));
+                }
+            }
+        }
+
+        if let Some(object) = instance.as_object() {
+            for (key, value) in object.iter() {
+                let value_path = pointer(instance_path, key);
+
+                if let Some(property) = schema.properties.get(key.as_str()) {
+                    errors.extend(self.check(property, &pointer(&pointer(schema_path, "properties"), key), value, &value_path));
+                }
+
+                for (pattern, property) in schema.pattern_properties.iter() {
+                    if self.regex(pattern).is_some_and(|regex| regex.is_match(key)) {
+                        errors.extend(self.check(property, &pointer(&pointer(schema_path, "patternProperties"), pattern), value, &value_path));
+                    }
+                }
+
+                if let Some(AdditionalProperties::Schema(additional)) = schema.additional_properties.as_ref() {
+                    if !self.declared(schema, key) {
+                        errors.extend(self.check(additional, &pointer(schema_path, "additionalProperties"), value, &value_path));
+                    }
+                }
+            }
+
+            for (trigger, dependent) in schema.dependent_schemas.iter().filter(|(trigger, _)| object.contains_key(trigger.as_str())) {
+                errors.extend(self.check(dependent, &pointer(&pointer(schema_path, "dependentSchemas"), trigger), instance, instance_path));
+            }
+        }
+
+        for (i, all) in schema.all_of.iter().enumerate() {
+            errors.extend(self.check(all, &pointer(&pointer(schema_path, "allOf"), &i.to_string()), instance, instance_path));
+        }
+
+        let mut fail = |keyword: &str, message: String| errors.push(ValidationError {
+            instance_path: instance_path.into(),
+            schema_path: pointer(schema_path, keyword),
+            message,
+        });
+
+        if !schema.any_of.is_empty() {
+            let matched = (0..schema.any_of.len())
+                .any(|i| self.check(&schema.any_of[i], &pointer(&pointer(schema_path, "anyOf"), &i.to_string()), instance, instance_path).is_empty());
+            if !matched {
+                fail("anyOf", String::from("expected a value matching at least one of the anyOf schemas"));
+            }
+        }
+
+        if !schema.one_of.is_empty() {
+            let matched = (0..schema.one_of.len())
+                .filter(|i| self.check(&schema.one_of[*i], &pointer(&pointer(schema_path, "oneOf"), &i.to_string()), instance, instance_path).is_empty())
+                .count();
+            if matched != 1 {
+                fail("oneOf", format!("expected a value matching exactly one of the oneOf schemas, it matches {}", matched));
+            }
+        }
+
+        if let Some(not) = schema.not.as_deref() {
+            if self.check(not, &pointer(schema_path, "not"), instance, instance_path).is_empty() {
+                fail("not", format!("invalid value: {}, expected a value the not schema rejects", instance));
+            }
+        }
+
+        if let Some(if_schema) = schema.if_schema.as_deref() {
+            let (keyword, branch) = match self.check(if_schema, &pointer(schema_path, "if"), instance, instance_path).is_empty() {
+                true => ("then", schema.then_schema.as_deref()),
+                false => ("else", schema.else_schema.as_deref()),
+            };
+            if let Some(branch) = branch {
+                errors.extend(self.check(branch, &pointer(schema_path, keyword), instance, instance_path));
+            }
+        }
+
+        errors
+    }
+
+    fn check_reference(&mut self, reference: &str, schema_path: &str, instance: &JsonValue, instance_path: &str) -> Vec<ValidationError> {
+        let following = (reference.to_string(), instance_path.to_string());
+        match self.root.pointer(reference) {
+            _ if self.references.contains(&following) => Vec::new(),
+            Some(target) => {
+                self.references.push(following);
+                let errors = self.check(target, &pointer(schema_path, "$ref"), instance, instance_path);
+                self.references.pop();
+                errors
+            },
+            None => vec![ValidationError {
+                instance_path: instance_path.into(),
+                schema_path: pointer(schema_path, "$ref"),
+                message: format!("unresolvable reference {}", reference),
+            }],
+        }
+    }
+
+    // Whether a property is covered by `properties` or `patternProperties`, and so isn't an additional property
+    fn declared(&mut self, schema: &Schema, key: &str) -> bool {
+        schema.properties.contains_key(key) || schema.pattern_properties.keys().any(|pattern| self.regex(pattern).is_some_and(|regex| regex.is_match(key)))
+    }
+
+    fn regex(&mut self, pattern: &str) -> Option<&Regex> {
+        if !self.patterns.contains_key(pattern) {
+            self.patterns.insert(pattern.to_string(), Regex::new(pattern).ok());
+        }
+        self.patterns[pattern].as_ref()
+    }
+}
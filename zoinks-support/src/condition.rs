@@ -0,0 +1,47 @@
+use serde_json::Value as JsonValue;
+
+/// Compares two JSON values the way JSON Schema does, so `1` and `1.0` are equal.
+pub fn json_eq(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Number(a), JsonValue::Number(b)) => a.as_f64() == b.as_f64(),
+        (JsonValue::Array(a), JsonValue::Array(b)) => a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| json_eq(a, b)),
+        (JsonValue::Object(a), JsonValue::Object(b)) => a.len() == b.len() && a.iter().all(|(k, a)| b.get(k).is_some_and(|b| json_eq(a, b))),
+        (a, b) => a == b,
+    }
+}
+
+/// Whether `value` equals the JSON text in `literal`, e.g. `"\"circle\""` or `"3"`.
+pub fn matches_literal(value: &JsonValue, literal: &str) -> bool {
+    match serde_json::from_str::<JsonValue>(literal) {
+        Ok(literal) => json_eq(value, &literal),
+        Err(_) => false,
+    }
+}
+
+/// The subset of an `if` schema the generator can evaluate: constant (or enumerated) property values and required
+/// properties.
+#[derive(Debug, Clone, Copy)]
+pub struct Condition<'a> {
+    /// Property name and the values it may take, as JSON text
+    pub properties: &'a [(&'a str, &'a [&'a str])],
+    pub required: &'a [&'a str],
+}
+
+impl<'a> Condition<'a> {
+    /// Whether `instance` is valid against the `if` schema.  As in JSON Schema, an absent property only fails the
+    /// condition when it is required, and a non-object instance passes it.
+    pub fn holds(&self, instance: &JsonValue) -> bool {
+        let object = match instance.as_object() {
+            Some(object) => object,
+            None => return true,
+        };
+
+        let values_match = self.properties.iter()
+            .all(|(name, literals)| match object.get(*name) {
+                Some(value) => literals.iter().any(|literal| matches_literal(value, literal)),
+                None => true,
+            });
+
+        values_match && self.required.iter().all(|name| object.contains_key(*name))
+    }
+}
@@ -4,11 +4,30 @@ use serde::{Deserialize, Deserializer};
 use serde::de::{self, SeqAccess, Visitor};
 
 mod numeric_validator;
-pub use numeric_validator::NumericValidatorConfig;
+pub use numeric_validator::{IntegerValidatorConfig, NumericValidatorConfig};
 
 mod string_validator;
 pub use string_validator::StringValidatorConfig;
 
+mod not_validator;
+pub use not_validator::{NotValidatorConfig, is_instance_of};
+
+// Deserialization entry points that report the path of the failing value
+mod path;
+pub use path::{PathError, from_reader, from_slice, from_str, from_value};
+
+// Evaluating simple conditional (if/then/else) schemas at runtime
+mod condition;
+pub use condition::{Condition, json_eq, matches_literal};
+
+// Optional properties that may also be null
+mod nullable;
+pub use nullable::Nullable;
+
+// Runtime validation of generated types
+mod validate;
+pub use validate::{Validate, ValidationErrors, Violation, field_path, index_path};
+
 // https://github.com/serde-rs/serde/issues/889
 pub fn string_or_vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where D: Deserializer<'de>
@@ -37,3 +56,14 @@ where D: Deserializer<'de>
 
     deserializer.deserialize_any(StringOrVec)
 }
+
+// Serde takes a missing `Option` to be `None`, which a required property that may be null mustn't be.  Used with
+// `#[serde(deserialize_with = "zoinks_support::required")]`, which reports the property as missing instead.
+pub fn required<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where D: Deserializer<'de>, T: Deserialize<'de>
+{
+    T::deserialize(deserializer)
+}
+
+#[cfg(test)]
+mod test;
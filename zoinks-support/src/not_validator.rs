@@ -0,0 +1,80 @@
+use std::fmt;
+
+use quote::{ToTokens, quote};
+use serde_json::Value as JsonValue;
+
+use crate::{ValidationErrors, matches_literal};
+
+/// Rejects the values a `not` schema describes: particular values (`not: {"enum": [...]}`) or whole types
+/// (`not: {"type": "null"}`).
+#[derive(Debug, Clone, Default)]
+pub struct NotValidatorConfig {
+    /// Forbidden values, as JSON text
+    pub values: Vec<String>,
+    /// Forbidden JSON Schema types, e.g. `null` or `integer`
+    pub types: Vec<String>,
+}
+
+impl ToTokens for NotValidatorConfig {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let values = &self.values;
+        let types = &self.types;
+
+        tokens.extend(quote!{
+            zoinks_support::NotValidatorConfig {
+                values: vec![#(String::from(#values)),*],
+                types: vec![#(String::from(#types)),*],
+            }
+        })
+    }
+}
+
+/// Whether `value` is an instance of the JSON Schema type `instance_type`, e.g. `integer`.
+pub fn is_instance_of(value: &JsonValue, instance_type: &str) -> bool {
+    match instance_type {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => false,
+    }
+}
+
+impl NotValidatorConfig {
+    /// Records a violation at `path` if `value` is one of the forbidden values or types.
+    pub fn check(&self, value: &JsonValue, path: &str, errors: &mut ValidationErrors) {
+        if !self.valid(value) {
+            errors.push(path, format!("invalid value: {}, expected {}", value, self));
+        }
+    }
+
+    pub fn valid(&self, value: &JsonValue) -> bool {
+        let forbidden_value = self.values.iter().any(|literal| matches_literal(value, literal));
+        let forbidden_type = self.types.iter().any(|instance_type| is_instance_of(value, instance_type));
+
+        !forbidden_value && !forbidden_type
+    }
+}
+
+/// Describes what's accepted, e.g. `a value other than "parent" or "datum"` or `a value that is not null`.
+impl fmt::Display for NotValidatorConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a value")?;
+
+        if !self.values.is_empty() {
+            write!(f, " other than {}", self.values.join(" or "))?;
+        }
+
+        if !self.types.is_empty() {
+            if !self.values.is_empty() {
+                f.write_str(" and")?;
+            }
+            write!(f, " that is not {}", self.types.join(" or "))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Validate, ValidationErrors};
+
+/// An optional property that may also be `null`, keeping the two apart so documents round-trip.
+///
+/// Use `#[serde(default)]` so a missing property deserializes as `Absent`, and
+/// `#[serde(skip_serializing_if = "Nullable::is_absent")]` so it stays missing when serialized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Nullable<T> {
+    #[default]
+    Absent,
+    Null,
+    Present(T),
+}
+
+impl<T> Nullable<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Self::Absent)
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn is_present(&self) -> bool {
+        matches!(self, Self::Present(_))
+    }
+
+    pub fn as_ref(&self) -> Nullable<&T> {
+        match self {
+            Self::Absent => Nullable::Absent,
+            Self::Null => Nullable::Null,
+            Self::Present(value) => Nullable::Present(value),
+        }
+    }
+
+    /// The value, if there is one, treating absent and null alike.
+    pub fn as_option(&self) -> Option<&T> {
+        match self {
+            Self::Present(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn into_option(self) -> Option<T> {
+        match self {
+            Self::Present(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// `None` is an explicit `null`.
+impl<T> From<Option<T>> for Nullable<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::Present(value),
+            None => Self::Null,
+        }
+    }
+}
+
+// Only called when the property is there at all
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Nullable<T> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        Option::<T>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl<T: Serialize> Serialize for Nullable<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+    {
+        match self {
+            Self::Present(value) => serializer.serialize_some(value),
+            _ => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<T: Validate> Validate for Nullable<T> {
+    fn validate_at(&self, path: &str, errors: &mut ValidationErrors) {
+        if let Self::Present(value) = self {
+            value.validate_at(path, errors);
+        }
+    }
+}
@@ -3,7 +3,9 @@ use std::fmt;
 use quote::{ToTokens, quote};
 use serde::de::{self, Unexpected, Visitor};
 
-#[derive(Debug)]
+use crate::ValidationErrors;
+
+#[derive(Debug, Clone, Default)]
 pub struct NumericValidatorConfig {
     pub min: Option<f64>,
     pub exclusive_min: Option<f64>,
@@ -11,17 +13,6 @@ pub struct NumericValidatorConfig {
     pub exclusive_max: Option<f64>,
 }
 
-impl Default for NumericValidatorConfig {
-    fn default() -> Self {
-        Self {
-            min: None,
-            exclusive_min: None,
-            max: None,
-            exclusive_max: None,
-        }
-    }
-}
-
 impl ToTokens for NumericValidatorConfig {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let min = match self.min.as_ref() {
@@ -56,7 +47,34 @@ impl ToTokens for NumericValidatorConfig {
 }
 
 impl NumericValidatorConfig {
-    fn valid(&self, n: f64) -> bool {
+    // The tightest lower bound, and whether it is exclusive
+    fn lower_bound(&self) -> Option<(f64, bool)> {
+        match (self.min, self.exclusive_min) {
+            (Some(min), Some(exclusive_min)) if exclusive_min >= min => Some((exclusive_min, true)),
+            (Some(min), _) => Some((min, false)),
+            (None, Some(exclusive_min)) => Some((exclusive_min, true)),
+            (None, None) => None,
+        }
+    }
+
+    // The tightest upper bound, and whether it is exclusive
+    fn upper_bound(&self) -> Option<(f64, bool)> {
+        match (self.max, self.exclusive_max) {
+            (Some(max), Some(exclusive_max)) if exclusive_max <= max => Some((exclusive_max, true)),
+            (Some(max), _) => Some((max, false)),
+            (None, Some(exclusive_max)) => Some((exclusive_max, true)),
+            (None, None) => None,
+        }
+    }
+
+    /// Records a violation at `path` if `n` is out of bounds.
+    pub fn check(&self, n: f64, path: &str, errors: &mut ValidationErrors) {
+        if !self.valid(n) {
+            errors.push(path, format!("invalid value: {}, expected {}", n, self));
+        }
+    }
+
+    pub fn valid(&self, n: f64) -> bool {
         if let Some(min) = self.min {
             if min > n {
                 return false
@@ -85,12 +103,36 @@ impl NumericValidatorConfig {
     }
 }
 
+impl NumericValidatorConfig {
+    // e.g. `a number in [0, 360]` or `an integer > 0`
+    fn describe(&self, f: &mut fmt::Formatter<'_>, noun: &str) -> fmt::Result {
+        match (self.lower_bound(), self.upper_bound()) {
+            (Some((min, min_excl)), Some((max, max_excl))) => {
+                let open = if min_excl { '(' } else { '[' };
+                let close = if max_excl { ')' } else { ']' };
+                write!(f, "{} in {}{}, {}{}", noun, open, min, max, close)
+            },
+            (Some((min, true)), None) => write!(f, "{} > {}", noun, min),
+            (Some((min, false)), None) => write!(f, "{} ≥ {}", noun, min),
+            (None, Some((max, true))) => write!(f, "{} < {}", noun, max),
+            (None, Some((max, false))) => write!(f, "{} ≤ {}", noun, max),
+            (None, None) => f.write_str(noun),
+        }
+    }
+}
+
+/// Describes the accepted range, e.g. `a number in [0, 360]` or `a number > 0`.
+impl fmt::Display for NumericValidatorConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.describe(f, "a number")
+    }
+}
+
 impl<'de> Visitor<'de> for NumericValidatorConfig {
     type Value = f64;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        let expected_string = format!("expected a number within bounds {:?}, {:?}", self.min, self.max);
-        formatter.write_str(&expected_string)
+        write!(formatter, "{}", self)
     }
 
     fn visit_i64<E>(self, n: i64) -> Result<Self::Value, E>
@@ -126,3 +168,72 @@ impl<'de> Visitor<'de> for NumericValidatorConfig {
         }
     }
 }
+
+/// Like `NumericValidatorConfig`, but only accepts integers.
+#[derive(Debug, Clone, Default)]
+pub struct IntegerValidatorConfig(pub NumericValidatorConfig);
+
+impl ToTokens for IntegerValidatorConfig {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let config = &self.0;
+
+        tokens.extend(quote!{
+            zoinks_support::IntegerValidatorConfig(#config)
+        })
+    }
+}
+
+impl IntegerValidatorConfig {
+    /// Records a violation at `path` if `n` is out of bounds.
+    pub fn check(&self, n: i64, path: &str, errors: &mut ValidationErrors) {
+        if !self.0.valid(n as f64) {
+            errors.push(path, format!("invalid value: {}, expected {}", n, self));
+        }
+    }
+}
+
+/// Describes the accepted range, e.g. `an integer in [0, 10]`.
+impl fmt::Display for IntegerValidatorConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.describe(f, "an integer")
+    }
+}
+
+impl<'de> Visitor<'de> for IntegerValidatorConfig {
+    type Value = i64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self)
+    }
+
+    fn visit_i64<E>(self, n: i64) -> Result<Self::Value, E>
+        where E: de::Error
+    {
+        if self.0.valid(n as f64) {
+            Ok(n)
+        } else {
+            Err(de::Error::invalid_value(Unexpected::Signed(n), &self))
+        }
+    }
+
+    fn visit_u64<E>(self, n: u64) -> Result<Self::Value, E>
+        where E: de::Error
+    {
+        if n <= i64::MAX as u64 && self.0.valid(n as f64) {
+            Ok(n as i64)
+        } else {
+            Err(de::Error::invalid_value(Unexpected::Unsigned(n), &self))
+        }
+    }
+
+    // JSON Schema counts 1.0 as an integer
+    fn visit_f64<E>(self, n: f64) -> Result<Self::Value, E>
+        where E: de::Error
+    {
+        if n.fract() == 0.0 && n.abs() < 9.2e18 && self.0.valid(n) {
+            Ok(n as i64)
+        } else {
+            Err(de::Error::invalid_value(Unexpected::Float(n), &self))
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::io::Read;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// A deserialization error annotated with the path of the value that failed, e.g.
+/// `encoding.x.scale.domainMid: invalid type: string "a", expected a number`.
+pub type PathError = serde_path_to_error::Error<serde_json::Error>;
+
+/// Like `serde_json::from_str`, but reports where in the document the error occurred.
+pub fn from_str<'de, T>(s: &'de str) -> Result<T, PathError>
+where T: Deserialize<'de>
+{
+    let mut deserializer = serde_json::Deserializer::from_str(s);
+    serde_path_to_error::deserialize(&mut deserializer)
+}
+
+/// Like `serde_json::from_slice`, but reports where in the document the error occurred.
+pub fn from_slice<'de, T>(v: &'de [u8]) -> Result<T, PathError>
+where T: Deserialize<'de>
+{
+    let mut deserializer = serde_json::Deserializer::from_slice(v);
+    serde_path_to_error::deserialize(&mut deserializer)
+}
+
+/// Like `serde_json::from_reader`, but reports where in the document the error occurred.
+pub fn from_reader<R, T>(reader: R) -> Result<T, PathError>
+where R: Read, T: DeserializeOwned
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    serde_path_to_error::deserialize(&mut deserializer)
+}
+
+/// Like `serde_json::from_value`, but reports where in the document the error occurred.
+pub fn from_value<T>(value: serde_json::Value) -> Result<T, PathError>
+where T: DeserializeOwned
+{
+    serde_path_to_error::deserialize(value)
+}
@@ -3,21 +3,14 @@ use std::fmt;
 use quote::{ToTokens, quote};
 use serde::de::{self, Unexpected, Visitor};
 
-#[derive(Debug)]
+use crate::ValidationErrors;
+
+#[derive(Debug, Clone, Default)]
 pub struct StringValidatorConfig {
     pub min_length: Option<u32>,
     pub max_length: Option<u32>,
 }
 
-impl Default for StringValidatorConfig {
-    fn default() -> Self {
-        Self {
-            min_length: None,
-            max_length: None,
-        }
-    }
-}
-
 impl ToTokens for StringValidatorConfig {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let min_length = match self.min_length.as_ref() {
@@ -40,8 +33,16 @@ impl ToTokens for StringValidatorConfig {
 }
 
 impl StringValidatorConfig {
-    fn valid(&self, s: &str) -> bool {
-        let len = s.len();
+    /// Records a violation at `path` if `s` is too short or too long.
+    pub fn check(&self, s: &str, path: &str, errors: &mut ValidationErrors) {
+        if !self.valid(s) {
+            errors.push(path, format!("invalid value: {:?}, expected {}", s, self));
+        }
+    }
+
+    pub fn valid(&self, s: &str) -> bool {
+        // JSON Schema measures length in characters, not bytes
+        let len = s.chars().count();
 
         if let Some(min_length) = self.min_length {
             if (min_length as usize) > len {
@@ -59,12 +60,31 @@ impl StringValidatorConfig {
     }
 }
 
+fn characters(n: u32) -> &'static str {
+    match n {
+        1 => "character",
+        _ => "characters",
+    }
+}
+
+/// Describes the accepted length, e.g. `a string of 1–64 characters`.
+impl fmt::Display for StringValidatorConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min_length, self.max_length) {
+            (Some(min), Some(max)) if min == max => write!(f, "a string of exactly {} {}", min, characters(min)),
+            (Some(min), Some(max)) => write!(f, "a string of {}–{} {}", min, max, characters(max)),
+            (Some(min), None) => write!(f, "a string of at least {} {}", min, characters(min)),
+            (None, Some(max)) => write!(f, "a string of at most {} {}", max, characters(max)),
+            (None, None) => f.write_str("a string"),
+        }
+    }
+}
+
 impl<'de> Visitor<'de> for StringValidatorConfig {
     type Value = String;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        let expected_string = format!("expected a string within length bounds {:?}, {:?}", self.min_length, self.max_length);
-        formatter.write_str(&expected_string)
+        write!(formatter, "{}", self)
     }
 
     fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
@@ -76,5 +96,4 @@ impl<'de> Visitor<'de> for StringValidatorConfig {
             Err(de::Error::invalid_value(Unexpected::Str(s), &self))
         }
     }
-
 }
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{IntegerValidatorConfig, NumericValidatorConfig, StringValidatorConfig};
+
+#[derive(Debug)]
+struct Angle(f64);
+
+impl<'de> Deserialize<'de> for Angle {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        let config = NumericValidatorConfig {
+            min: Some(0.0),
+            exclusive_min: None,
+            max: Some(360.0),
+            exclusive_max: None,
+        };
+
+        deserializer.deserialize_any(config).map(Self)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Mark {
+    angle: Angle,
+}
+
+#[test]
+fn numeric_expecting() {
+    let config = NumericValidatorConfig { min: Some(0.0), max: Some(360.0), ..Default::default() };
+    assert_eq!(config.to_string(), "a number in [0, 360]");
+
+    let config = NumericValidatorConfig { min: Some(0.0), exclusive_min: Some(0.0), exclusive_max: Some(1.5), ..Default::default() };
+    assert_eq!(config.to_string(), "a number in (0, 1.5)");
+
+    let config = NumericValidatorConfig { exclusive_min: Some(-1.0), ..Default::default() };
+    assert_eq!(config.to_string(), "a number > -1");
+
+    let config = NumericValidatorConfig { max: Some(10.0), ..Default::default() };
+    assert_eq!(config.to_string(), "a number ≤ 10");
+}
+
+#[test]
+fn integer_accepts_whole_numbers() {
+    let config = || IntegerValidatorConfig(NumericValidatorConfig { min: Some(0.0), max: Some(10.0), ..Default::default() });
+    assert_eq!(config().to_string(), "an integer in [0, 10]");
+
+    let value = serde_json::json!(3.0);
+    assert_eq!(value.deserialize_any(config()).unwrap(), 3);
+
+    let value = serde_json::json!(3.5);
+    let err = value.deserialize_any(config()).unwrap_err();
+    assert_eq!(err.to_string(), "invalid value: floating point `3.5`, expected an integer in [0, 10]");
+
+    let value = serde_json::json!(11);
+    assert!(value.deserialize_any(config()).is_err());
+}
+
+#[test]
+fn string_expecting() {
+    let config = StringValidatorConfig { min_length: Some(1), max_length: Some(64) };
+    assert_eq!(config.to_string(), "a string of 1–64 characters");
+
+    let config = StringValidatorConfig { min_length: Some(1), max_length: None };
+    assert_eq!(config.to_string(), "a string of at least 1 character");
+
+    let config = StringValidatorConfig { min_length: Some(2), max_length: Some(2) };
+    assert_eq!(config.to_string(), "a string of exactly 2 characters");
+}
+
+#[test]
+fn string_length_counts_characters() {
+    let config = StringValidatorConfig { min_length: None, max_length: Some(2) };
+    let value = serde_json::Value::String("ñü".into());
+    assert!(value.deserialize_any(config).is_ok());
+}
+
+#[test]
+fn accepts_in_range() {
+    let marks = crate::from_str::<Vec<Mark>>(r#"[{"angle": 0}, {"angle": 359.5}]"#).unwrap();
+    assert_eq!(marks.iter().map(|mark| mark.angle.0).collect::<Vec<_>>(), vec![0.0, 359.5]);
+}
+
+#[test]
+fn error_includes_path() {
+    let err = crate::from_str::<HashMap<String, Vec<Mark>>>(r#"{"marks": [{"angle": 90}, {"angle": 400}]}"#).unwrap_err();
+    assert_eq!(err.path().to_string(), "marks[1].angle");
+    assert!(err.to_string().starts_with("marks[1].angle: invalid value: integer `400`, expected a number in [0, 360]"), "{}", err);
+}
+
+impl crate::Validate for Angle {
+    fn validate_at(&self, path: &str, errors: &mut crate::ValidationErrors) {
+        let config = NumericValidatorConfig { min: Some(0.0), max: Some(360.0), ..Default::default() };
+        config.check(self.0, path, errors);
+    }
+}
+
+impl crate::Validate for Mark {
+    fn validate_at(&self, path: &str, errors: &mut crate::ValidationErrors) {
+        crate::Validate::validate_at(&self.angle, &crate::field_path(path, "angle"), errors);
+    }
+}
+
+#[test]
+fn validate_collects_every_violation() {
+    use crate::Validate;
+
+    let marks = Some(vec![Mark { angle: Angle(-1.0) }, Mark { angle: Angle(90.0) }, Mark { angle: Angle(400.0) }]);
+    let errors = marks.validate().unwrap_err();
+    let paths = errors.violations().iter().map(|violation| violation.path.as_str()).collect::<Vec<_>>();
+    assert_eq!(paths, vec!["[0].angle", "[2].angle"]);
+    assert_eq!(errors.violations()[1].to_string(), "[2].angle: invalid value: 400, expected a number in [0, 360]");
+
+    assert!(vec![Mark { angle: Angle(0.0) }].validate().is_ok());
+}
+
+#[derive(Debug, PartialEq, Deserialize, serde::Serialize)]
+struct Title {
+    #[serde(default, skip_serializing_if = "crate::Nullable::is_absent")]
+    text: crate::Nullable<String>,
+}
+
+#[test]
+fn nullable_round_trips() {
+    for json in &[r#"{}"#, r#"{"text":null}"#, r#"{"text":"hi"}"#] {
+        let title = serde_json::from_str::<Title>(json).unwrap();
+        assert_eq!(serde_json::to_string(&title).unwrap(), *json);
+    }
+
+    let title = serde_json::from_str::<Title>(r#"{"text":null}"#).unwrap();
+    assert!(title.text.is_null());
+    assert_eq!(title.text.as_option(), None);
+}
@@ -0,0 +1,168 @@
+use std::fmt;
+
+/// A single failed constraint and the path of the value that failed it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// Path in the same form `PathError` reports it, e.g. `marks[1].angle`.  Empty for the root.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.is_empty() {
+            true => write!(f, "{}", self.message),
+            false => write!(f, "{}: {}", self.path, self.message),
+        }
+    }
+}
+
+/// Every violation found while validating a value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationErrors {
+    violations: Vec<Violation>,
+}
+
+impl ValidationErrors {
+    pub fn push<M>(&mut self, path: &str, message: M)
+    where M: Into<String>
+    {
+        self.violations.push(Violation {
+            path: path.into(),
+            message: message.into(),
+        });
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.violations.len()
+    }
+
+    pub fn into_result(self) -> Result<(), Self> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl IntoIterator for ValidationErrors {
+    type Item = Violation;
+    type IntoIter = std::vec::IntoIter<Violation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.violations.into_iter()
+    }
+}
+
+/// Runtime validation for generated types.
+///
+/// Deserialization already enforces most constraints, but values built in Rust code bypass it.  The generator
+/// implements this trait for every struct, enum and newtype it emits.
+pub trait Validate {
+    /// Checks every constraint, collecting all violations rather than stopping at the first.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        self.validate_at("", &mut errors);
+        errors.into_result()
+    }
+
+    /// Checks every constraint, recording violations relative to `path`.
+    fn validate_at(&self, path: &str, errors: &mut ValidationErrors);
+}
+
+/// Appends an object key to a path, e.g. `marks[1]` + `angle` gives `marks[1].angle`.
+pub fn field_path(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.into(),
+        false => format!("{}.{}", path, key),
+    }
+}
+
+/// Appends an array index to a path, e.g. `marks` + `1` gives `marks[1]`.
+pub fn index_path(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
+
+macro_rules! validate_always {
+    ($($t:ty),*) => {
+        $(
+            impl Validate for $t {
+                fn validate_at(&self, _path: &str, _errors: &mut ValidationErrors) {}
+            }
+        )*
+    };
+}
+
+validate_always!(bool, f32, f64, i32, i64, u32, u64, String, serde_json::Value);
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate_at(&self, path: &str, errors: &mut ValidationErrors) {
+        if let Some(value) = self {
+            value.validate_at(path, errors);
+        }
+    }
+}
+
+impl<T: Validate> Validate for Box<T> {
+    fn validate_at(&self, path: &str, errors: &mut ValidationErrors) {
+        (**self).validate_at(path, errors);
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_at(&self, path: &str, errors: &mut ValidationErrors) {
+        for (i, value) in self.iter().enumerate() {
+            value.validate_at(&index_path(path, i), errors);
+        }
+    }
+}
+
+// Arrays with a fixed number of items become tuples
+macro_rules! validate_tuple {
+    ($(($($i:tt $t:ident),+)),*) => {
+        $(
+            impl<$($t: Validate),+> Validate for ($($t,)+) {
+                fn validate_at(&self, path: &str, errors: &mut ValidationErrors) {
+                    $(self.$i.validate_at(&index_path(path, $i), errors);)+
+                }
+            }
+        )*
+    };
+}
+
+validate_tuple!(
+    (0 A),
+    (0 A, 1 B),
+    (0 A, 1 B, 2 C),
+    (0 A, 1 B, 2 C, 3 D),
+    (0 A, 1 B, 2 C, 3 D, 4 E),
+    (0 A, 1 B, 2 C, 3 D, 4 E, 5 F),
+    (0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G),
+    (0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H),
+    (0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I),
+    (0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J),
+    (0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K),
+    (0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H, 8 I, 9 J, 10 K, 11 L)
+);
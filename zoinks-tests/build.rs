@@ -0,0 +1,134 @@
+// Generates types for every case of JSON-Schema-Test-Suite under suite/tests (see suite/README.md) into
+// $OUT_DIR/suite.rs, for the harness in src/test.rs
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use serde::Deserialize;
+
+use zoinks_lib::Draft;
+
+// Test cases don't always say which draft they're written for, the directory they're in does
+const DRAFTS: &[(&str, Draft)] = &[
+    ("draft4", Draft::Draft4),
+    ("draft7", Draft::Draft7),
+    ("draft2019-09", Draft::Draft201909),
+    ("draft2020-12", Draft::Draft202012),
+];
+
+#[derive(Deserialize)]
+struct Case {
+    description: String,
+    schema: serde_json::Value,
+    tests: Vec<Test>,
+}
+
+#[derive(Deserialize)]
+struct Test {
+    description: String,
+    data: serde_json::Value,
+    valid: bool,
+}
+
+// Only the cases that are left out are needed here, the rest is read by src/test.rs
+#[derive(Deserialize)]
+struct ExpectedFailures {
+    skipped: BTreeMap<String, Vec<String>>,
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=suite");
+    println!("cargo:rerun-if-changed=expected-failures.json");
+
+    let expected = std::fs::read_to_string("expected-failures.json").expect("Unable to read expected-failures.json");
+    let expected = serde_json::from_str::<ExpectedFailures>(&expected).expect("Unable to parse expected-failures.json");
+
+    // Generator panics are recorded as failures, there's no need to see them too
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let mut modules = Vec::new();
+    let mut cases = Vec::new();
+
+    for (draft, dialect) in DRAFTS {
+        let mut files = std::fs::read_dir(Path::new("suite/tests").join(draft))
+            .expect("Unable to read the test suite")
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+            .collect::<Vec<_>>();
+        files.sort();
+
+        for path in files {
+            let stem = path.file_stem().unwrap().to_string_lossy();
+            let file = format!("{}/{}.json", draft, stem);
+            let file_cases = serde_json::from_str::<Vec<Case>>(&std::fs::read_to_string(&path).unwrap())
+                .unwrap_or_else(|e| panic!("Unable to parse {}: {}", file, e));
+
+            for (i, case) in file_cases.iter().enumerate() {
+                let module = format!("{}_{}_{}", draft, stem, i).to_lowercase().replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+                let module = Ident::new(&module, Span::call_site());
+
+                // Cases whose generated code doesn't compile can only be left out
+                let skipped = expected.skipped.get(&file).is_some_and(|cases| cases.contains(&case.description));
+                let generated = match skipped {
+                    true => Err(String::from("not generated, see expected-failures.json")),
+                    false => generate(&case.schema, *dialect),
+                };
+
+                let deserializes = match generated {
+                    Ok(tokens) => {
+                        modules.push(quote!{
+                            pub mod #module {
+                                #tokens
+
+                                pub fn deserializes(json: &str) -> bool {
+                                    serde_json::from_str::<TopLevel>(json).is_ok()
+                                }
+                            }
+                        });
+                        quote!{ Ok(#module::deserializes) }
+                    },
+                    Err(reason) => quote!{ Err(#reason) },
+                };
+
+                let tests = case.tests.iter().map(|test| {
+                    let description = &test.description;
+                    let data = test.data.to_string();
+                    let valid = test.valid;
+                    quote!{ Test { description: #description, data: #data, valid: #valid } }
+                });
+
+                let description = &case.description;
+                cases.push(quote!{
+                    Case {
+                        file: #file,
+                        description: #description,
+                        deserializes: #deserializes,
+                        tests: &[#(#tests),*],
+                    }
+                });
+            }
+        }
+    }
+
+    let _ = std::panic::take_hook();
+
+    let suite = quote!{
+        #(#modules)*
+
+        pub const CASES: &[Case] = &[#(#cases),*];
+    };
+    // Formatted, so compiler errors point somewhere readable
+    let suite = prettyplease::unparse(&syn::parse2(suite).expect("Generated code should parse"));
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out_dir).join("suite.rs"), suite).unwrap();
+}
+
+// The generated code for a schema, or why there isn't any
+fn generate(schema: &serde_json::Value, draft: Draft) -> Result<TokenStream, String> {
+    let schema = schema.to_string();
+    let schema = serde_json::from_str::<zoinks_lib::Schema>(&schema).map_err(|e| format!("schema doesn't parse: {}", e))?;
+
+    let options = zoinks_lib::Options { draft: Some(draft), ..Default::default() };
+    std::panic::catch_unwind(|| zoinks_lib::genimpl_with_options(&schema, &options).0).map_err(|_| String::from("generator panicked"))
+}
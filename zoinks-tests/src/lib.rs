@@ -0,0 +1,25 @@
+//! Runs JSON-Schema-Test-Suite (see suite/README.md) against the types zoinks generates.  build.rs generates a module
+//! for each case, and the harness in test.rs checks that every valid instance deserializes and every invalid one is
+//! rejected.
+
+/// A schema and the instances to try against it
+pub struct Case {
+    /// e.g. `draft7/minLength.json`
+    pub file: &'static str,
+    pub description: &'static str,
+    /// Whether the generated `TopLevel` deserializes some JSON, or why nothing was generated
+    pub deserializes: Result<fn(&str) -> bool, &'static str>,
+    pub tests: &'static [Test],
+}
+
+pub struct Test {
+    pub description: &'static str,
+    /// JSON text
+    pub data: &'static str,
+    pub valid: bool,
+}
+
+include!(concat!(env!("OUT_DIR"), "/suite.rs"));
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+
+use crate::CASES;
+
+// file -> case -> tests
+type Tests = BTreeMap<String, BTreeMap<String, Vec<String>>>;
+
+// Tests known to fail.  Valid instances that are rejected are kept apart from invalid ones that are accepted: the
+// generated types are lenient so there are plenty of the latter, but the former mean a document that's fine can't be
+// read at all.
+#[derive(serde::Deserialize)]
+struct ExpectedFailures {
+    // file -> cases that aren't generated at all (by build.rs), because their generated code wouldn't compile
+    skipped: BTreeMap<String, Vec<String>>,
+    valid: Tests,
+    invalid: Tests,
+}
+
+fn expected_failures() -> ExpectedFailures {
+    serde_json::from_str(include_str!("../expected-failures.json")).unwrap()
+}
+
+// Prints a pass rate for each keyword (`cargo test -p zoinks-tests -- --nocapture` to see it) and fails on anything
+// that doesn't match expected-failures.json, including tests listed there that now pass
+#[test]
+fn json_schema_test_suite() {
+    let expected = expected_failures();
+
+    // file -> passed, total
+    let mut report : BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    let mut unexpected = Vec::new();
+
+    for case in CASES {
+        let skipped = expected.skipped.get(case.file).is_some_and(|cases| cases.iter().any(|skipped| skipped == case.description));
+
+        for test in case.tests {
+            let expected_tests = match test.valid {
+                true => &expected.valid,
+                false => &expected.invalid,
+            };
+            let expected_tests = expected_tests.get(case.file).and_then(|cases| cases.get(case.description));
+            let outcome = match (case.deserializes, test.valid) {
+                (Err(reason), _) => Err(reason),
+                (Ok(deserializes), true) if !deserializes(test.data) => Err("valid instance was rejected"),
+                (Ok(deserializes), false) if deserializes(test.data) => Err("invalid instance was accepted"),
+                (Ok(_), _) => Ok(()),
+            };
+            let expected_failure = skipped || expected_tests.is_some_and(|tests| tests.iter().any(|expected| expected == test.description));
+
+            let (passed, total) = report.entry(case.file).or_default();
+            *total += 1;
+
+            match (outcome, expected_failure) {
+                (Ok(()), false) => *passed += 1,
+                (Ok(()), true) => {
+                    *passed += 1;
+                    unexpected.push(format!("{} / {} / {}: passes now, remove it from expected-failures.json", case.file, case.description, test.description));
+                },
+                (Err(_), true) => {},
+                (Err(reason), false) => unexpected.push(format!("{} / {} / {}: {}", case.file, case.description, test.description, reason)),
+            }
+        }
+    }
+
+    println!("{:<45} {:>7} {:>7}", "keyword", "passed", "total");
+    for (file, (passed, total)) in report.iter() {
+        println!("{:<45} {:>7} {:>7} {:>5.0}%", file, passed, total, 100.0 * *passed as f64 / *total as f64);
+    }
+    let (passed, total) = report.values().fold((0, 0), |(passed, total), (p, t)| (passed + p, total + t));
+    println!("{:<45} {:>7} {:>7} {:>5.0}%", "all", passed, total, 100.0 * passed as f64 / total as f64);
+
+    assert!(unexpected.is_empty(), "{} unexpected results:\n{}", unexpected.len(), unexpected.join("\n"));
+}
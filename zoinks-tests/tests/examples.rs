@@ -0,0 +1,52 @@
+//! Compiles the bindings for every schema in schema-examples/ and reads the documents under examples/ with them.
+
+use std::path::Path;
+
+use zoinks_support::Validate;
+
+// The macro reads schemas relative to the workspace root, which is where cargo runs rustc
+mod address { zoinks_codegen::schema2struct!("schema-examples/address.schema.json"); }
+mod calendar { zoinks_codegen::schema2struct!("schema-examples/calendar.schema.json"); }
+mod card { zoinks_codegen::schema2struct!("schema-examples/card.schema.json"); }
+mod geographical_location { zoinks_codegen::schema2struct!("schema-examples/geographical-location.schema.json"); }
+mod vega { zoinks_codegen::schema2struct!("schema-examples/vega-v5.schema.json"); }
+mod vega_lite { zoinks_codegen::schema2struct!("schema-examples/vega-lite-v5.schema.json"); }
+
+// Every .json file in a directory under examples/
+fn documents(directory: &str) -> Vec<(std::path::PathBuf, String)> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples").join(directory);
+    let mut files = std::fs::read_dir(&directory)
+        .unwrap_or_else(|e| panic!("Unable to read {}: {}", directory.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect::<Vec<_>>();
+    files.sort();
+    assert!(!files.is_empty(), "No documents in {}", directory.display());
+
+    files.into_iter()
+        .map(|path| {
+            let json = std::fs::read_to_string(&path).unwrap();
+            (path, json)
+        })
+        .collect()
+}
+
+macro_rules! examples {
+    ($test:ident, $module:ident, $directory:literal) => {
+        #[test]
+        fn $test() {
+            for (file, json) in documents($directory) {
+                let document = zoinks_support::from_str::<$module::TopLevel>(&json)
+                    .unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+                document.validate().unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+            }
+        }
+    };
+}
+
+examples!(address_examples, address, "address");
+examples!(calendar_examples, calendar, "calendar");
+examples!(card_examples, card, "card");
+examples!(geographical_location_examples, geographical_location, "geographical-location");
+examples!(vega_examples, vega, "vega");
+examples!(vega_lite_examples, vega_lite, "vega-lite");
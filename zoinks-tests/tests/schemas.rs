@@ -0,0 +1,124 @@
+//! Compiles the small schemas in schemas/, each of which exercises one keyword, and checks which documents their types
+//! accept.
+
+mod dependent_schemas { zoinks_codegen::schema2struct!("zoinks-tests/schemas/dependent-schemas.json"); }
+mod enums { zoinks_codegen::schema2struct!("zoinks-tests/schemas/enums.json"); }
+mod not { zoinks_codegen::schema2struct!("zoinks-tests/schemas/not.json"); }
+mod nullable { zoinks_codegen::schema2struct!("zoinks-tests/schemas/nullable.json"); }
+mod numeric_enums { zoinks_codegen::schema2struct!("zoinks-tests/schemas/numeric-enums.json"); }
+mod optional_arrays { zoinks_codegen::schema2struct!("zoinks-tests/schemas/optional-arrays.json"); }
+mod untyped_conditional { zoinks_codegen::schema2struct!("zoinks-tests/schemas/untyped-conditional.json"); }
+mod untyped_properties { zoinks_codegen::schema2struct!("zoinks-tests/schemas/untyped-properties.json"); }
+
+// Whether `json` deserializes as T
+fn accepts<T: serde::de::DeserializeOwned>(json: &str) -> bool {
+    zoinks_support::from_str::<T>(json).is_ok()
+}
+
+#[test]
+fn dependent_schemas() {
+    use dependent_schemas::TopLevel;
+
+    assert!(accepts::<TopLevel>(r#"{ "name": "Shaggy" }"#));
+    assert!(accepts::<TopLevel>(r#"{ "credit_card": 5555555555554444, "billing_address": "Crystal Cove" }"#));
+    assert!(!accepts::<TopLevel>(r#"{ "name": "Shaggy", "credit_card": 5555555555554444 }"#));
+    assert!(!accepts::<TopLevel>(r#"{ "credit_card": 5555555555554444, "billing_address": "" }"#));
+}
+
+#[test]
+fn enums() {
+    use enums::{Autosize, Mixed};
+
+    // Members of every kind, with no type to say which kinds there are
+    assert!(accepts::<Autosize>(r#""pad""#));
+    assert!(!accepts::<Autosize>(r#""shrink""#));
+    assert!(matches!(zoinks_support::from_str::<Mixed>("[1, 2]"), Ok(Mixed::Array3)));
+    for member in &[r#""a""#, "true", "null", r#"{ "k": "v" }"#] {
+        assert!(accepts::<Mixed>(member), "{}", member);
+    }
+    for other in &[r#""b""#, "false", "[2, 1]", r#"{ "k": "w" }"#, "1"] {
+        assert!(!accepts::<Mixed>(other), "{}", other);
+    }
+    assert_eq!(serde_json::to_string(&Mixed::Object4).unwrap(), r#"{"k":"v"}"#);
+}
+
+#[test]
+fn not() {
+    use not::TopLevel;
+
+    // multipleOf can't be expressed, so it isn't enforced rather than forbidding every number
+    assert!(accepts::<TopLevel>(r#"{ "odd": 3 }"#));
+    assert!(accepts::<TopLevel>(r#"{ "not_a": 1 }"#));
+    assert!(!accepts::<TopLevel>(r#"{ "not_a": "a" }"#));
+    assert!(accepts::<TopLevel>(r#"{ "not_string": 1 }"#));
+    assert!(!accepts::<TopLevel>(r#"{ "not_string": "a" }"#));
+}
+
+#[test]
+fn nullable() {
+    use nullable::TopLevel;
+    use zoinks_support::Nullable;
+
+    // A required nullable property is an Option, an optional one keeps missing and null apart
+    let chart = zoinks_support::from_str::<TopLevel>(r#"{ "name": null, "axis": null }"#).unwrap();
+    assert!(chart.name.is_none());
+    assert!(matches!(chart.axis, Nullable::Null));
+    assert!(matches!(chart.label, Nullable::Absent));
+    let chart = zoinks_support::from_str::<TopLevel>(r#"{ "name": "Daphne", "axis": { "title": "x" }, "label": 1 }"#).unwrap();
+    assert!(chart.name.is_some());
+    assert!(matches!(chart.axis, Nullable::Present(_)));
+    assert!(matches!(chart.label, Nullable::Present(_)));
+    assert!(!accepts::<TopLevel>("{}"));
+    assert!(!accepts::<TopLevel>(r#"{ "name": null, "label": true }"#));
+}
+
+#[test]
+fn numeric_enums() {
+    use numeric_enums::Nums;
+
+    // Members are matched by value, so 1 and 1.0 are the same member and "1" isn't one
+    assert!(matches!(zoinks_support::from_str::<Nums>("1.0"), Ok(Nums::N1)));
+    assert!(matches!(zoinks_support::from_str::<Nums>("-0.5"), Ok(Nums::Neg0_5)));
+    for member in &["1", "-2", "0.5"] {
+        assert!(accepts::<Nums>(member), "{}", member);
+    }
+    for other in &["2", "0.25", r#""1""#] {
+        assert!(!accepts::<Nums>(other), "{}", other);
+    }
+    assert_eq!(serde_json::to_string(&Nums::Neg2).unwrap(), "-2");
+    assert_eq!(serde_json::to_string(&Nums::N0_5).unwrap(), "0.5");
+}
+
+#[test]
+fn optional_arrays() {
+    use optional_arrays::TopLevel;
+
+    // An empty array is still a property, so it's told apart from one that was left out
+    let empty = zoinks_support::from_str::<TopLevel>(r#"{ "tags": [] }"#).unwrap();
+    assert!(matches!(empty.tags, Some(tags) if tags.is_empty()));
+    assert!(accepts::<TopLevel>(r#"{ "name": "Fred", "tags": [] }"#));
+    assert!(!accepts::<TopLevel>("{}"));
+    assert!(!accepts::<TopLevel>(r#"{ "name": "Fred" }"#));
+}
+
+#[test]
+fn untyped_conditional() {
+    use untyped_conditional::TopLevel;
+
+    // Branches that aren't about objects aren't enforced, but nothing valid may be turned away
+    assert!(accepts::<TopLevel>("-1"));
+    assert!(accepts::<TopLevel>("4"));
+    assert!(accepts::<TopLevel>(r#""not a number""#));
+}
+
+#[test]
+fn untyped_properties() {
+    use untyped_properties::TopLevel;
+
+    // Properties only constrain objects, so the schema (and the parent it refers back to) can be anything else
+    assert!(accepts::<TopLevel>(r#"{ "name": "Velma", "parent": { "parent": false } }"#));
+    assert!(accepts::<TopLevel>(r#"{ "parent": null }"#));
+    assert!(accepts::<TopLevel>("[1, 2]"));
+    assert!(!accepts::<TopLevel>(r#"{ "name": 1 }"#));
+    assert!(!accepts::<TopLevel>(r#"{ "parent": { "age": 1 } }"#));
+}